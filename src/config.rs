@@ -6,17 +6,365 @@ pub struct WeatherConfig {
     pub url: String,
 }
 
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interface: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vpn_interfaces: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interval: Option<u64>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationsConfig {
+    pub apps: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy)]
+pub enum ClockFormat {
+    #[serde(rename = "auto")]
+    Auto,
+    #[serde(rename = "12h")]
+    Hour12,
+    #[serde(rename = "24h")]
+    Hour24,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extended: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub world_clocks: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clock_format: Option<ClockFormat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CalendarConfig {
+    pub files: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alerts: Option<Vec<u8>>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TimerConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notify: Option<bool>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Default, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum CommandMode {
+    #[default]
+    Interval,
+    Lines,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Default, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum ValueParser {
+    Integer,
+    #[default]
+    String,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandConfig {
+    pub command: String,
+    pub data_type: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mode: Option<CommandMode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interval: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parser: Option<ValueParser>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub json_path: Option<String>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Default, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum FileFormat {
+    #[default]
+    Text,
+    Bytes,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FileConfig {
+    pub path: String,
+    pub data_type: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<FileFormat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub debounce: Option<u64>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpApiConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MqttSubscription {
+    pub topic: String,
+    pub data_type: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parser: Option<ValueParser>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub json_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device: Option<String>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MqttConfig {
+    pub host: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub topic_prefix: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subscriptions: Option<Vec<MqttSubscription>>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SunConfig {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionStateConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pause_providers: Option<bool>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AppLayerRule {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub app: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub process: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<Pattern>,
+    pub layer: u8,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AppLayersConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_layer: Option<u8>,
+    pub rules: Vec<AppLayerRule>,
+}
+
+/// Regular expression compiled when config is loaded, so invalid patterns are reported at startup
+#[derive(Clone, Debug)]
+pub struct Pattern(regex::Regex);
+
+impl Pattern {
+    pub fn is_match(&self, value: &str) -> bool {
+        return self.0.is_match(value);
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Pattern {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let value: String = serde::Deserialize::deserialize(deserializer)?;
+        return regex::Regex::new(&value).map(Pattern).map_err(serde::de::Error::custom);
+    }
+}
+
+impl serde::Serialize for Pattern {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        return serializer.serialize_str(self.0.as_str());
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LayoutEntry {
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aliases: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub patterns: Option<Vec<Pattern>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+#[serde(untagged)]
+pub enum LayoutConfig {
+    Id(String),
+    Entry(LayoutEntry),
+}
+
+/// Deserialized by hand instead of `untagged`, which hides errors of the entry, e.g. an invalid pattern
+impl<'de> serde::Deserialize<'de> for LayoutConfig {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct LayoutConfigVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for LayoutConfigVisitor {
+            type Value = LayoutConfig;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                return formatter.write_str("layout name or object");
+            }
+
+            fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<Self::Value, E> {
+                return Ok(LayoutConfig::Id(value.to_string()));
+            }
+
+            fn visit_map<A: serde::de::MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+                let entry = serde::Deserialize::deserialize(serde::de::value::MapAccessDeserializer::new(map))?;
+                return Ok(LayoutConfig::Entry(entry));
+            }
+        }
+
+        return deserializer.deserialize_any(LayoutConfigVisitor);
+    }
+}
+
+impl LayoutConfig {
+    pub fn id(&self) -> &str {
+        match self {
+            LayoutConfig::Id(id) => id,
+            LayoutConfig::Entry(entry) => &entry.id,
+        }
+    }
+
+    pub fn aliases(&self) -> &[String] {
+        match self {
+            LayoutConfig::Id(_) => &[],
+            LayoutConfig::Entry(entry) => entry.aliases.as_deref().unwrap_or_default(),
+        }
+    }
+
+    pub fn label(&self) -> Option<&str> {
+        match self {
+            LayoutConfig::Id(_) => None,
+            LayoutConfig::Entry(entry) => entry.label.as_deref(),
+        }
+    }
+
+    pub fn has_name(&self, value: &str) -> bool {
+        return self.id() == value || self.aliases().iter().any(|x| x == value);
+    }
+
+    pub fn patterns(&self) -> &[Pattern] {
+        match self {
+            LayoutConfig::Id(_) => &[],
+            LayoutConfig::Entry(entry) => entry.patterns.as_deref().unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Default, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum LayoutBackend {
+    #[default]
+    Auto,
+    X11,
+    Sway,
+    Hyprland,
+    Kde,
+    Gnome,
+}
+
 #[derive(serde::Deserialize, serde::Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Config {
     pub devices: Vec<Device>,
-    pub layouts: Vec<String>,
+    pub layouts: Vec<LayoutConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub layout_fallback: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub layout_name: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub layout_backend: Option<LayoutBackend>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lock_state: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reconnect_delay: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub time: Option<TimeConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub weather: Option<WeatherConfig>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub extended_media: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub battery: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub network: Option<NetworkConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audio_devices: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub focused_apps: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub app_layers: Option<AppLayersConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notifications: Option<NotificationsConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_state: Option<SessionStateConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub calendar: Option<CalendarConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timer: Option<TimerConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sun: Option<SunConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commands: Option<Vec<CommandConfig>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub files: Option<Vec<FileConfig>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub http_api: Option<HttpApiConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mqtt: Option<MqttConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dbus_service: Option<bool>,
 }
 
 #[derive(serde::Deserialize, serde::Serialize)]
@@ -50,12 +398,32 @@ pub fn load_config(path: PathBuf) -> &'static Config {
             usage: None,
             usage_page: None,
         }],
-        layouts: vec!["en".to_string()],
+        layouts: vec![LayoutConfig::Id("en".to_string())],
+        layout_fallback: None,
+        layout_name: None,
+        layout_backend: None,
+        lock_state: None,
         reconnect_delay: None,
+        time: None,
         weather: Some(WeatherConfig {
             url: "wttr.in/Hamburg?format=%t".to_string(),
         }),
         extended_media: None,
+        battery: None,
+        network: None,
+        audio_devices: None,
+        focused_apps: None,
+        app_layers: None,
+        notifications: None,
+        session_state: None,
+        calendar: None,
+        timer: None,
+        sun: None,
+        commands: None,
+        files: None,
+        http_api: None,
+        mqtt: None,
+        dbus_service: None,
     };
 
     if let Ok(file) = std::fs::read_to_string(&path) {
@@ -1,4 +1,6 @@
 #[cfg(not(target_os = "macos"))]
+// some data types are sent only by Linux providers
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
 pub enum DataType {
     Time = 0xAA, // random value that does not conflict with VIA/VIAL, must match firmware
     Volume,
@@ -8,9 +10,32 @@ pub enum DataType {
     Weather = 0xAF,
     MediaPlayerLinux = 0xB0,
     MediaExtended = 0xB1,
+    Battery = 0xB2,
+    Network = 0xB3,
+    Microphone = 0xB4,
+    AudioDevice = 0xB5,
+    LayoutName = 0xB6,
+    LockState = 0xB7,
+    FocusedApp = 0xB8,
+    SetLayer = 0xB9,
+    Notifications = 0xBA,
+    SessionState = 0xBB,
+    Calendar = 0xBC,
+    CalendarAlert = 0xBD,
+    Timer = 0xBE,
+    TimerDone = 0xBF,
+    TimeExtended = 0xC0,
+    WorldClock = 0xC1,
+    TimeFormatted = 0xC2,
+    Sun = 0xC3,
+    Text = 0xC4,
 
     RelayFromDevice = 0xCC,
     RelayToDevice,
+
+    AudioDeviceCommand = 0xD0,
+    TimerCommand = 0xD1,
+    MediaCommand = 0xD2,
 }
 
 #[cfg(target_os = "macos")]
@@ -20,7 +45,35 @@ pub enum DataType {
     Layout,
     Spotify = 0xAE,
     Weather = 0xAF,
+    Battery = 0xB2,
+    LayoutName = 0xB6,
+    Calendar = 0xBC,
+    CalendarAlert = 0xBD,
+    Timer = 0xBE,
+    TimerDone = 0xBF,
+    TimeExtended = 0xC0,
+    WorldClock = 0xC1,
+    TimeFormatted = 0xC2,
+    Sun = 0xC3,
+    Text = 0xC4,
 
     RelayFromDevice = 0xCC,
     RelayToDevice,
+
+    TimerCommand = 0xD1,
+}
+
+/// Only these data types are read from devices, everything else (e.g. VIA responses) is ignored
+pub fn is_device_to_host(value: u8) -> bool {
+    #[cfg(target_os = "linux")]
+    if value == DataType::AudioDeviceCommand as u8 {
+        return true;
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    if value == DataType::MediaCommand as u8 {
+        return true;
+    }
+
+    return value == DataType::RelayFromDevice as u8 || value == DataType::TimerCommand as u8;
 }
@@ -0,0 +1,200 @@
+#![cfg(target_os = "linux")]
+use dbus::arg::AppendAll;
+use dbus::channel::{BusType, Channel};
+use dbus::message::MessageType;
+use dbus::Message;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::data_type::DataType;
+use crate::keyboard::{self, Keyboard, SendFrameError};
+
+const NAME: &str = "org.qmk.HidHost";
+const PATH: &str = "/org/qmk/HidHost";
+const INTERFACE: &str = "org.qmk.HidHost";
+
+const INTROSPECTION: &str = r#"<!DOCTYPE node PUBLIC "-//freedesktop//DTD D-BUS Object Introspection 1.0//EN"
+ "http://www.freedesktop.org/standards/dbus/1.0/introspect.dtd">
+<node>
+  <interface name="org.qmk.HidHost">
+    <method name="ListDevices">
+      <arg name="devices" type="a(sb)" direction="out"/>
+    </method>
+    <method name="SendFrame">
+      <arg name="device" type="s" direction="in"/>
+      <arg name="frame" type="ay" direction="in"/>
+    </method>
+    <method name="SetLayer">
+      <arg name="device" type="s" direction="in"/>
+      <arg name="layer" type="y" direction="in"/>
+    </method>
+    <signal name="DeviceConnected">
+      <arg name="device" type="s"/>
+    </signal>
+    <signal name="DeviceDisconnected">
+      <arg name="device" type="s"/>
+    </signal>
+    <signal name="FrameReceived">
+      <arg name="device" type="s"/>
+      <arg name="frame" type="ay"/>
+    </signal>
+  </interface>
+  <interface name="org.freedesktop.DBus.Introspectable">
+    <method name="Introspect">
+      <arg name="xml" type="s" direction="out"/>
+    </method>
+  </interface>
+</node>"#;
+
+/// Fails if the name is owned by another instance
+fn connect() -> Result<Channel, dbus::Error> {
+    let channel = Channel::get_private(BusType::Session)?;
+    // 4 - DBUS_NAME_FLAG_DO_NOT_QUEUE
+    let message = Message::new_method_call(
+        "org.freedesktop.DBus",
+        "/org/freedesktop/DBus",
+        "org.freedesktop.DBus",
+        "RequestName",
+    )
+    .map_err(|e| dbus::Error::new_failed(&e))?
+    .append2(NAME, 4u32);
+    let reply = channel.send_with_reply_and_block(message, Duration::from_millis(5000))?;
+    // 1 - DBUS_REQUEST_NAME_REPLY_PRIMARY_OWNER
+    if reply.read1::<u32>().ok() != Some(1) {
+        return Err(dbus::Error::new_failed(&format!("{} is already owned", NAME)));
+    }
+    return Ok(channel);
+}
+
+/// Empty device name sends the frame to all devices
+fn send_frame(
+    message: &Message,
+    data: Vec<u8>,
+    device_name: &str,
+    keyboards: &[Keyboard],
+    host_to_device_sender: &broadcast::Sender<Vec<u8>>,
+    device_frame_sender: &broadcast::Sender<(String, Vec<u8>)>,
+) -> Message {
+    let device_name = (!device_name.is_empty()).then_some(device_name);
+    return match keyboard::send_frame(data, device_name, keyboards, host_to_device_sender, device_frame_sender) {
+        Ok(_) => message.method_return(),
+        Err(SendFrameError::InvalidLength) => message.error(&"org.qmk.HidHost.Error.InvalidFrame".into(), c"frame must be 1 to 32 bytes"),
+        Err(SendFrameError::UnknownDevice) => message.error(&"org.qmk.HidHost.Error.UnknownDevice".into(), c"unknown device"),
+        Err(SendFrameError::NotConnected) => message.error(&"org.qmk.HidHost.Error.NotConnected".into(), c"device is not connected"),
+    };
+}
+
+fn handle_method_call(
+    message: &Message,
+    keyboards: &[Keyboard],
+    host_to_device_sender: &broadcast::Sender<Vec<u8>>,
+    device_frame_sender: &broadcast::Sender<(String, Vec<u8>)>,
+) -> Message {
+    if message.path().as_deref() != Some(PATH) {
+        return message.error(&"org.freedesktop.DBus.Error.UnknownObject".into(), c"unknown object");
+    }
+
+    let invalid_args = |e: dbus::arg::TypeMismatchError| {
+        let description = std::ffi::CString::new(e.to_string()).unwrap_or_default();
+        message.error(&"org.freedesktop.DBus.Error.InvalidArgs".into(), &description)
+    };
+
+    // interface is optional in method calls
+    return match (message.interface().as_deref().unwrap_or(INTERFACE), message.member().as_deref()) {
+        ("org.freedesktop.DBus.Introspectable", Some("Introspect")) => message.method_return().append1(INTROSPECTION),
+        (INTERFACE, Some("ListDevices")) => {
+            let devices = keyboards
+                .iter()
+                .map(|x| (x.get_name().to_string(), x.is_connected()))
+                .collect::<Vec<_>>();
+            message.method_return().append1(devices)
+        }
+        (INTERFACE, Some("SendFrame")) => match message.read2::<&str, Vec<u8>>() {
+            Ok((device_name, frame)) => send_frame(message, frame, device_name, keyboards, host_to_device_sender, device_frame_sender),
+            Err(e) => invalid_args(e),
+        },
+        (INTERFACE, Some("SetLayer")) => match message.read2::<&str, u8>() {
+            Ok((device_name, layer)) => send_frame(
+                message,
+                vec![DataType::SetLayer as u8, layer],
+                device_name,
+                keyboards,
+                host_to_device_sender,
+                device_frame_sender,
+            ),
+            Err(e) => invalid_args(e),
+        },
+        _ => message.error(&"org.freedesktop.DBus.Error.UnknownMethod".into(), c"unknown method"),
+    };
+}
+
+fn send_signal(channel: &Channel, name: &'static str, values: impl AppendAll) {
+    let mut signal = Message::signal(&PATH.into(), &INTERFACE.into(), &name.into());
+    signal.append_all(values);
+    if channel.send(signal).is_err() {
+        tracing::error!("D-Bus service failed to send {} signal", name);
+    }
+}
+
+/// Service keeps running regardless of connected devices
+pub fn start(
+    keyboards: Arc<Vec<Keyboard>>,
+    host_to_device_sender: broadcast::Sender<Vec<u8>>,
+    device_frame_sender: broadcast::Sender<(String, Vec<u8>)>,
+    named_device_to_host_sender: broadcast::Sender<(String, Vec<u8>)>,
+) {
+    std::thread::spawn(move || {
+        let channel = match connect() {
+            Ok(channel) => Arc::new(channel),
+            Err(e) => {
+                tracing::error!("D-Bus service can not be registered: {}", e);
+                return;
+            }
+        };
+
+        tracing::info!("D-Bus service {} is registered", NAME);
+        start_frame_signals(channel.clone(), &named_device_to_host_sender);
+        let mut connected_states = vec![false; keyboards.len()];
+        loop {
+            match channel.blocking_pop_message(Duration::from_millis(100)) {
+                Ok(Some(message)) if message.msg_type() == MessageType::MethodCall => {
+                    let reply = handle_method_call(&message, &keyboards, &host_to_device_sender, &device_frame_sender);
+                    if channel.send(reply).is_err() {
+                        tracing::error!("D-Bus service failed to send reply");
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::error!("D-Bus service is disconnected: {}", e);
+                    break;
+                }
+            }
+
+            for (keyboard, is_connected) in keyboards.iter().zip(connected_states.iter_mut()) {
+                if keyboard.is_connected() != *is_connected {
+                    *is_connected = !*is_connected;
+                    let name = if *is_connected { "DeviceConnected" } else { "DeviceDisconnected" };
+                    send_signal(&channel, name, (keyboard.get_name(),));
+                }
+            }
+
+            channel.flush();
+        }
+    });
+}
+
+/// Frames are received in another thread, so none of them are missed while waiting for method calls
+fn start_frame_signals(channel: Arc<Channel>, named_device_to_host_sender: &broadcast::Sender<(String, Vec<u8>)>) {
+    let mut named_device_to_host_receiver = named_device_to_host_sender.subscribe();
+    std::thread::spawn(move || loop {
+        match named_device_to_host_receiver.blocking_recv() {
+            Ok((device_name, data)) => {
+                send_signal(&channel, "FrameReceived", (device_name, data));
+                channel.flush();
+            }
+            Err(RecvError::Lagged(count)) => tracing::warn!("D-Bus service skipped {} frames from devices", count),
+            Err(RecvError::Closed) => break,
+        }
+    });
+}
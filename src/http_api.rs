@@ -0,0 +1,299 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+use crate::config::HttpApiConfig;
+use crate::data_type::DataType;
+use crate::keyboard::{self, Keyboard, SendFrameError};
+
+const MAX_BODY_SIZE: usize = 4096;
+/// Request line and headers, longer requests are cut and rejected as invalid
+const MAX_HEADER_SIZE: usize = 8192;
+
+/// Browser extensions are allowed, web pages are not, so any open site can not send data to the keyboard
+const ALLOWED_ORIGINS: [&str; 3] = ["chrome-extension://", "moz-extension://", "safari-web-extension://"];
+
+struct Request {
+    method: String,
+    path: String,
+    headers: HashMap<String, String>,
+    body: Vec<u8>,
+}
+
+struct Response {
+    status: &'static str,
+    body: String,
+}
+
+impl Response {
+    fn new(status: &'static str, body: &str) -> Self {
+        return Self {
+            status,
+            body: body.to_string(),
+        };
+    }
+}
+
+/// Returns error response if request is invalid or its body is too large
+fn read_request(stream: &TcpStream) -> Result<Request, Response> {
+    let invalid = || Response::new("400 Bad Request", r#"{"error":"invalid request"}"#);
+    let mut reader = BufReader::new(stream.take((MAX_HEADER_SIZE + MAX_BODY_SIZE) as u64));
+    let mut line = String::new();
+    reader.read_line(&mut line).map_err(|_| invalid())?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next().ok_or_else(invalid)?.to_string();
+    let path = parts.next().ok_or_else(invalid)?.to_string();
+
+    let mut headers = HashMap::new();
+    let mut header_size = line.len();
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).map_err(|_| invalid())?;
+        header_size += line.len();
+        if header_size > MAX_HEADER_SIZE || !line.ends_with('\n') {
+            return Err(invalid());
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.insert(name.trim().to_lowercase(), value.trim().to_string());
+        }
+    }
+
+    let length = match headers.get("content-length") {
+        Some(length) => length.parse::<usize>().map_err(|_| invalid())?,
+        None => 0,
+    };
+    if length > MAX_BODY_SIZE {
+        return Err(Response::new("413 Payload Too Large", r#"{"error":"request body is too large"}"#));
+    }
+
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).map_err(|_| invalid())?;
+
+    return Ok(Request {
+        method,
+        path,
+        headers,
+        body,
+    });
+}
+
+fn write_response(mut stream: &TcpStream, response: &Response) {
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        response.body.len(),
+        response.body
+    );
+    if let Err(e) = stream.write_all(response.as_bytes()) {
+        tracing::error!("HTTP API failed to send response: {}", e);
+    }
+}
+
+/// Compares all bytes, so response time does not tell how much of the token is correct
+fn is_token_valid(authorization: Option<&String>, token: &str) -> bool {
+    let expected = format!("Bearer {}", token);
+    let actual = authorization.map(|x| x.as_bytes()).unwrap_or_default();
+    if actual.len() != expected.len() {
+        return false;
+    }
+
+    let difference = actual.iter().zip(expected.as_bytes()).fold(0, |result, (a, b)| result | (a ^ b));
+    return std::hint::black_box(difference) == 0;
+}
+
+fn get_status(keyboards: &[Keyboard]) -> Response {
+    let devices = keyboards
+        .iter()
+        .map(|x| serde_json::json!({ "name": x.get_name(), "connected": x.is_connected() }))
+        .collect::<Vec<_>>();
+    return Response::new("200 OK", &serde_json::json!({ "devices": devices }).to_string());
+}
+
+/// `[DataType::Text, length, text]`, text is cut to fit into a single report
+fn get_text_data(text: &[u8]) -> Vec<u8> {
+    let text = String::from_utf8_lossy(text);
+    let mut data = text.trim().as_bytes().to_vec();
+    data.truncate(30);
+    data.insert(0, data.len() as u8);
+    data.insert(0, DataType::Text as u8);
+    return data;
+}
+
+fn send_frame(
+    data: Vec<u8>,
+    device_name: Option<&str>,
+    keyboards: &[Keyboard],
+    host_to_device_sender: &broadcast::Sender<Vec<u8>>,
+    device_frame_sender: &broadcast::Sender<(String, Vec<u8>)>,
+) -> Response {
+    return match keyboard::send_frame(data, device_name, keyboards, host_to_device_sender, device_frame_sender) {
+        Ok(_) => Response::new("200 OK", r#"{"ok":true}"#),
+        Err(SendFrameError::InvalidLength) => Response::new("400 Bad Request", r#"{"error":"frame must be 1 to 32 bytes"}"#),
+        Err(SendFrameError::UnknownDevice) => Response::new("404 Not Found", r#"{"error":"unknown device"}"#),
+        Err(SendFrameError::NotConnected) => Response::new("503 Service Unavailable", r#"{"error":"device is not connected"}"#),
+    };
+}
+
+fn handle_request(
+    request: &Request,
+    config: &HttpApiConfig,
+    keyboards: &[Keyboard],
+    host_to_device_sender: &broadcast::Sender<Vec<u8>>,
+    device_frame_sender: &broadcast::Sender<(String, Vec<u8>)>,
+) -> Response {
+    if let Some(origin) = request.headers.get("origin") {
+        if !ALLOWED_ORIGINS.iter().any(|x| origin.starts_with(x)) {
+            return Response::new("403 Forbidden", r#"{"error":"origin is not allowed"}"#);
+        }
+    }
+
+    if let Some(token) = &config.token {
+        if !is_token_valid(request.headers.get("authorization"), token) {
+            return Response::new("401 Unauthorized", r#"{"error":"invalid token"}"#);
+        }
+    }
+
+    let path = request.path.split('?').next().unwrap_or_default();
+    let segments = path.split('/').filter(|x| !x.is_empty()).collect::<Vec<_>>();
+    let send =
+        |data: Vec<u8>, device_name: Option<&str>| send_frame(data, device_name, keyboards, host_to_device_sender, device_frame_sender);
+
+    return match (request.method.as_str(), segments.as_slice()) {
+        ("GET", ["status"]) => get_status(keyboards),
+        ("POST", ["frames"]) => send(request.body.clone(), None),
+        ("POST", ["text"]) => send(get_text_data(&request.body), None),
+        ("POST", ["devices", name, "frames"]) => send(request.body.clone(), Some(name)),
+        ("POST", ["devices", name, "text"]) => send(get_text_data(&request.body), Some(name)),
+        _ => Response::new("404 Not Found", r#"{"error":"not found"}"#),
+    };
+}
+
+fn handle_connection(
+    stream: TcpStream,
+    config: &HttpApiConfig,
+    keyboards: &[Keyboard],
+    host_to_device_sender: &broadcast::Sender<Vec<u8>>,
+    device_frame_sender: &broadcast::Sender<(String, Vec<u8>)>,
+) {
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(5)));
+    let _ = stream.set_write_timeout(Some(Duration::from_secs(5)));
+    let response = match read_request(&stream) {
+        Ok(request) => {
+            tracing::debug!("HTTP API: {} {}", request.method, request.path);
+            handle_request(&request, config, keyboards, host_to_device_sender, device_frame_sender)
+        }
+        Err(response) => response,
+    };
+    write_response(&stream, &response);
+}
+
+/// Listens on localhost only, every connection is handled on its own thread, so a slow client does not block others
+pub fn start(
+    config: HttpApiConfig,
+    keyboards: Arc<Vec<Keyboard>>,
+    host_to_device_sender: broadcast::Sender<Vec<u8>>,
+    device_frame_sender: broadcast::Sender<(String, Vec<u8>)>,
+) {
+    let port = config.port.unwrap_or(8741);
+    let listener = match TcpListener::bind((Ipv4Addr::LOCALHOST, port)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            tracing::error!("HTTP API can not listen on port {}: {}", port, e);
+            return;
+        }
+    };
+
+    tracing::info!("HTTP API is listening on http://127.0.0.1:{}", port);
+    let config = Arc::new(config);
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let config = config.clone();
+            let keyboards = keyboards.clone();
+            let host_to_device_sender = host_to_device_sender.clone();
+            let device_frame_sender = device_frame_sender.clone();
+            std::thread::spawn(move || {
+                handle_connection(stream, &config, &keyboards, &host_to_device_sender, &device_frame_sender);
+            });
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sends raw request through a real socket and returns the parsed request or error status
+    fn read_raw_request(raw: &[u8]) -> Result<Request, &'static str> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        client.write_all(raw).unwrap();
+        client.shutdown(std::net::Shutdown::Write).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        return read_request(&stream).map_err(|x| x.status);
+    }
+
+    #[test]
+    fn reads_requests_with_limited_size() {
+        let request = read_raw_request(b"POST /text HTTP/1.1\r\nContent-Length: 5\r\nAuthorization: Bearer x\r\n\r\nHello").unwrap();
+        assert_eq!((request.method.as_str(), request.path.as_str()), ("POST", "/text"));
+        assert_eq!(request.headers.get("authorization").map(|x| x.as_str()), Some("Bearer x"));
+        assert_eq!(request.body, b"Hello");
+
+        let request = format!("POST /frames HTTP/1.1\r\nContent-Length: {}\r\n\r\n", MAX_BODY_SIZE + 1);
+        assert_eq!(read_raw_request(request.as_bytes()).err(), Some("413 Payload Too Large"));
+
+        let request = format!("GET /status HTTP/1.1\r\nCookie: {}\r\n\r\n", "x".repeat(MAX_HEADER_SIZE));
+        assert_eq!(read_raw_request(request.as_bytes()).err(), Some("400 Bad Request"));
+        assert_eq!(
+            read_raw_request(b"POST /text HTTP/1.1\r\nContent-Length: 10\r\n\r\nHi").err(),
+            Some("400 Bad Request")
+        );
+        assert_eq!(read_raw_request(b"GET /status HTTP/1.1\r\n").err(), Some("400 Bad Request"));
+    }
+
+    #[test]
+    fn checks_token() {
+        let header = |value: &str| Some(value.to_string());
+        assert!(is_token_valid(header("Bearer secret").as_ref(), "secret"));
+        assert!(!is_token_valid(header("Bearer secreT").as_ref(), "secret"));
+        assert!(!is_token_valid(header("Bearer secret2").as_ref(), "secret"));
+        assert!(!is_token_valid(None, "secret"));
+    }
+
+    #[test]
+    fn rejects_requests_from_web_pages_and_without_token() {
+        let config = HttpApiConfig {
+            port: None,
+            token: Some("secret".to_string()),
+        };
+        let (host_to_device_sender, _) = broadcast::channel::<Vec<u8>>(1);
+        let (device_frame_sender, _) = broadcast::channel::<(String, Vec<u8>)>(1);
+        let get_status = |headers: &[(&str, &str)]| {
+            let request = Request {
+                method: "GET".to_string(),
+                path: "/status".to_string(),
+                headers: headers.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect(),
+                body: vec![],
+            };
+            return handle_request(&request, &config, &[], &host_to_device_sender, &device_frame_sender).status;
+        };
+
+        assert_eq!(get_status(&[("authorization", "Bearer secret")]), "200 OK");
+        assert_eq!(get_status(&[("authorization", "Bearer wrong")]), "401 Unauthorized");
+        assert_eq!(
+            get_status(&[("authorization", "Bearer secret"), ("origin", "https://example.com")]),
+            "403 Forbidden"
+        );
+        assert_eq!(
+            get_status(&[("authorization", "Bearer secret"), ("origin", "moz-extension://id")]),
+            "200 OK"
+        );
+    }
+}
@@ -2,10 +2,11 @@ use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
 use std::sync::Arc;
 
 use hidapi::{DeviceInfo, HidApi, HidDevice};
-use tokio::sync::{broadcast, mpsc};
+use tokio::sync::broadcast::{self, error::TryRecvError};
+use tokio::sync::mpsc;
 
 use crate::config::Device;
-use crate::data_type::DataType;
+use crate::data_type::is_device_to_host;
 
 pub struct Keyboard {
     name: String,
@@ -28,6 +29,14 @@ impl Keyboard {
         };
     }
 
+    pub fn get_name(&self) -> &str {
+        return &self.name;
+    }
+
+    pub fn is_connected(&self) -> bool {
+        return self.is_connected.load(Relaxed);
+    }
+
     fn get_device_info(hid_api: &HidApi, product_id: &u16, usage: &u16, usage_page: &u16) -> Option<DeviceInfo> {
         let devices = hid_api.device_list();
         for device_info in devices {
@@ -42,7 +51,9 @@ impl Keyboard {
     pub fn connect(
         &self,
         host_to_device_sender: broadcast::Sender<Vec<u8>>,
+        device_frame_sender: broadcast::Sender<(String, Vec<u8>)>,
         device_to_host_sender: broadcast::Sender<Vec<u8>>,
+        named_device_to_host_sender: broadcast::Sender<(String, Vec<u8>)>,
         is_connected_sender: mpsc::Sender<bool>,
     ) {
         let name = self.name.clone();
@@ -63,7 +74,7 @@ impl Keyboard {
                     loop {
                         match device_info.open_device(&hid_api) {
                             Ok(device) => {
-                                start_write(&name, device, &is_connected, &host_to_device_sender);
+                                start_write(&name, device, &is_connected, &host_to_device_sender, &device_frame_sender);
                                 break;
                             }
                             Err(err) => tracing::error!("{}", err),
@@ -73,7 +84,7 @@ impl Keyboard {
                     loop {
                         match device_info.open_device(&hid_api) {
                             Ok(device) => {
-                                start_read(&name, device, &is_connected, &device_to_host_sender);
+                                start_read(&name, device, &is_connected, &device_to_host_sender, &named_device_to_host_sender);
                                 break;
                             }
                             Err(err) => tracing::error!("{}", err),
@@ -103,31 +114,94 @@ impl Keyboard {
     }
 }
 
-fn start_write(name: &String, device: HidDevice, is_connected: &Arc<AtomicBool>, host_to_device_sender: &broadcast::Sender<Vec<u8>>) {
-    let name = name.clone();
+#[derive(Debug)]
+pub enum SendFrameError {
+    InvalidLength,
+    UnknownDevice,
+    NotConnected,
+}
+
+/// Frame is sent to all devices if there is no name, otherwise only to the named device
+pub fn send_frame(
+    data: Vec<u8>,
+    device_name: Option<&str>,
+    keyboards: &[Keyboard],
+    host_to_device_sender: &broadcast::Sender<Vec<u8>>,
+    device_frame_sender: &broadcast::Sender<(String, Vec<u8>)>,
+) -> Result<(), SendFrameError> {
+    if data.is_empty() || data.len() > 32 {
+        return Err(SendFrameError::InvalidLength);
+    }
+
+    let is_sent = match device_name {
+        Some(name) => match keyboards.iter().find(|x| x.get_name() == name) {
+            Some(keyboard) => keyboard.is_connected() && device_frame_sender.send((name.to_string(), data)).is_ok(),
+            None => return Err(SendFrameError::UnknownDevice),
+        },
+        // nothing is subscribed when no device is connected
+        None => host_to_device_sender.send(data).is_ok(),
+    };
+
+    return if is_sent { Ok(()) } else { Err(SendFrameError::NotConnected) };
+}
+
+/// Writes data sent to all devices and data routed to this device by its name
+fn start_write(
+    name: &str,
+    device: HidDevice,
+    is_connected: &Arc<AtomicBool>,
+    host_to_device_sender: &broadcast::Sender<Vec<u8>>,
+    device_frame_sender: &broadcast::Sender<(String, Vec<u8>)>,
+) {
+    let name = name.to_string();
     let is_connected = is_connected.clone();
     let mut host_to_device_receiver = host_to_device_sender.subscribe();
+    let mut device_frame_receiver = device_frame_sender.subscribe();
     std::thread::spawn(move || loop {
-        tracing::debug!("{}: waiting for data to send...", name);
-        std::thread::sleep(std::time::Duration::from_millis(10)); // lowers host CPU usage by order of magnitude
-
-        if let Ok(mut received) = host_to_device_receiver.blocking_recv() {
-            tracing::info!("{}: sending {:?}", name, received);
-            received.truncate(32);
-            received.resize_with(32, Default::default);
-            received.insert(0, 0);
-            if let Err(_) = device.write(received.as_mut()) {
-                is_connected.store(false, Relaxed);
-                break;
+        let received = match host_to_device_receiver.try_recv() {
+            Ok(data) => Some(data),
+            Err(TryRecvError::Lagged(count)) => {
+                tracing::warn!("{}: {} frames were skipped", name, count);
+                None
             }
+            Err(_) => match device_frame_receiver.try_recv() {
+                Ok((device_name, data)) if device_name == name => Some(data),
+                Err(TryRecvError::Lagged(count)) => {
+                    tracing::warn!("{}: {} device frames were skipped", name, count);
+                    None
+                }
+                _ => None,
+            },
+        };
+
+        let Some(mut received) = received else {
+            std::thread::sleep(std::time::Duration::from_millis(10)); // lowers host CPU usage by order of magnitude
+            continue;
+        };
+
+        tracing::info!("{}: sending {:?}", name, received);
+        received.truncate(32);
+        received.resize_with(32, Default::default);
+        received.insert(0, 0);
+        if device.write(received.as_mut()).is_err() {
+            is_connected.store(false, Relaxed);
+            break;
         }
     });
 }
 
-fn start_read(name: &String, device: HidDevice, is_connected: &Arc<AtomicBool>, device_to_host_sender: &broadcast::Sender<Vec<u8>>) {
-    let name = name.clone();
+/// Device-to-host data is also sent with the device name for consumers which tell devices apart
+fn start_read(
+    name: &str,
+    device: HidDevice,
+    is_connected: &Arc<AtomicBool>,
+    device_to_host_sender: &broadcast::Sender<Vec<u8>>,
+    named_device_to_host_sender: &broadcast::Sender<(String, Vec<u8>)>,
+) {
+    let name = name.to_string();
     let is_connected = is_connected.clone();
     let device_to_host_sender = device_to_host_sender.clone();
+    let named_device_to_host_sender = named_device_to_host_sender.clone();
     let mut data = [0u8; 32];
     std::thread::spawn(move || loop {
         tracing::debug!("{}: waiting for data from keyboard...", name);
@@ -135,8 +209,9 @@ fn start_read(name: &String, device: HidDevice, is_connected: &Arc<AtomicBool>,
 
         if let Ok(result) = device.read(data.as_mut()) {
             tracing::debug!("{}: received {:?}", name, data);
-            if result > 0 && data[0] == DataType::RelayFromDevice as u8 {
+            if result > 0 && is_device_to_host(data[0]) {
                 let _ = device_to_host_sender.send(data.to_vec());
+                let _ = named_device_to_host_sender.send((name.clone(), data.to_vec()));
             }
         } else {
             is_connected.store(false, Relaxed);
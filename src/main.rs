@@ -5,7 +5,10 @@
 
 mod config;
 mod data_type;
+mod dbus_service;
+mod http_api;
 mod keyboard;
+mod mqtt;
 mod providers;
 mod utils;
 
@@ -13,16 +16,28 @@ use config::load_config;
 use keyboard::Keyboard;
 
 #[cfg(not(any(target_os = "linux", target_os = "macos")))]
-use providers::{_base::Provider, layout::LayoutProvider, relay::RelayProvider, time::TimeProvider, volume::VolumeProvider};
+use providers::{
+    _base::Provider, battery::BatteryProvider, calendar::CalendarProvider, command::CommandProvider, file::FileProvider,
+    layout::LayoutProvider, relay::RelayProvider, sun::SunProvider, time::TimeProvider, timer::TimerProvider, volume::VolumeProvider,
+};
 
 #[cfg(any(target_os = "linux", target_os = "macos"))]
 use providers::{
-    _base::Provider, layout::LayoutProvider, relay::RelayProvider, time::TimeProvider, volume::VolumeProvider, weather::WeatherProvider,
+    _base::Provider, battery::BatteryProvider, calendar::CalendarProvider, command::CommandProvider, file::FileProvider,
+    layout::LayoutProvider, relay::RelayProvider, sun::SunProvider, time::TimeProvider, timer::TimerProvider, volume::VolumeProvider,
+    weather::WeatherProvider,
 };
 
+use std::sync::Arc;
 use tokio::sync::{broadcast, mpsc};
 use utils::print_hids::print_unique_hid_devices;
 
+#[cfg(target_os = "linux")]
+use providers::{
+    audio_device::AudioDeviceProvider, focused_app::FocusedAppProvider, network::NetworkProvider, notifications::NotificationsProvider,
+    session_state::SessionStateProvider,
+};
+
 #[cfg(not(target_os = "macos"))]
 use providers::media::MediaProvider;
 
@@ -44,6 +59,9 @@ struct Args {
     print_hids: bool,
 }
 
+/// Providers send several frames at once (e.g. layout index and name), device writes them one by one
+const HOST_TO_DEVICE_CAPACITY: usize = 64;
+
 fn main() {
     let env_filter = tracing_subscriber::EnvFilter::builder()
         .with_default_directive(tracing::level_filters::LevelFilter::INFO.into())
@@ -52,8 +70,13 @@ fn main() {
     let _ = tracing::subscriber::set_global_default(tracing_subscriber);
 
     let (is_connected_sender, is_connected_receiver) = mpsc::channel::<bool>(1);
-    let (host_to_device_sender, _) = broadcast::channel::<Vec<u8>>(1);
-    let (device_to_host_sender, _) = broadcast::channel::<Vec<u8>>(1);
+    let (host_to_device_sender, _) = broadcast::channel::<Vec<u8>>(HOST_TO_DEVICE_CAPACITY);
+    // last frames of all MQTT subscriptions are sent again at once when a device is connected
+    let (device_frame_sender, _) = broadcast::channel::<(String, Vec<u8>)>(32);
+    // commands from encoders can come in quick succession, each of them is handled
+    let (device_to_host_sender, _) = broadcast::channel::<Vec<u8>>(16);
+    // consumers forward every frame with the name of the device that sent it, so none of them should be dropped
+    let (named_device_to_host_sender, _) = broadcast::channel::<(String, Vec<u8>)>(16);
 
     let args = Args::parse();
     if args.print_hids {
@@ -61,12 +84,52 @@ fn main() {
     }
     let config = load_config(args.config.unwrap_or("./qmk-hid-host.json".into()));
     let reconnect_delay = config.reconnect_delay.unwrap_or(5000);
+    let mut keyboards = vec![];
     for device in &config.devices {
         let host_to_device_sender = host_to_device_sender.clone();
+        let device_frame_sender = device_frame_sender.clone();
         let device_to_host_sender = device_to_host_sender.clone();
+        let named_device_to_host_sender = named_device_to_host_sender.clone();
         let is_connected_sender = is_connected_sender.clone();
         let keyboard = Keyboard::new(device, reconnect_delay);
-        keyboard.connect(host_to_device_sender, device_to_host_sender, is_connected_sender);
+        keyboard.connect(
+            host_to_device_sender,
+            device_frame_sender,
+            device_to_host_sender,
+            named_device_to_host_sender,
+            is_connected_sender,
+        );
+        keyboards.push(keyboard);
+    }
+
+    let keyboards = Arc::new(keyboards);
+    if let Some(http_api_config) = &config.http_api {
+        http_api::start(
+            http_api_config.clone(),
+            keyboards.clone(),
+            host_to_device_sender.clone(),
+            device_frame_sender.clone(),
+        );
+    }
+
+    if let Some(mqtt_config) = &config.mqtt {
+        mqtt::start(
+            mqtt_config.clone(),
+            keyboards.clone(),
+            host_to_device_sender.clone(),
+            device_frame_sender.clone(),
+            named_device_to_host_sender.clone(),
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    if config.dbus_service.unwrap_or(false) {
+        dbus_service::start(
+            keyboards.clone(),
+            host_to_device_sender.clone(),
+            device_frame_sender.clone(),
+            named_device_to_host_sender.clone(),
+        );
     }
 
     run(host_to_device_sender, device_to_host_sender, is_connected_receiver);
@@ -77,13 +140,43 @@ fn get_providers(
     host_to_device_sender: &broadcast::Sender<Vec<u8>>,
     device_to_host_sender: &broadcast::Sender<Vec<u8>>,
 ) -> Vec<Box<dyn Provider>> {
-    return vec![
-        TimeProvider::new(host_to_device_sender.clone()),
+    let mut providers: Vec<Box<dyn Provider>> = vec![
+        TimeProvider::new(host_to_device_sender.clone(), config::get_config().time.clone().unwrap_or_default()),
         VolumeProvider::new(host_to_device_sender.clone()),
         LayoutProvider::new(host_to_device_sender.clone()),
-        MediaProvider::new(host_to_device_sender.clone()),
+        MediaProvider::new(host_to_device_sender.clone(), device_to_host_sender.clone()),
         RelayProvider::new(host_to_device_sender.clone(), device_to_host_sender.clone()),
     ];
+
+    if config::get_config().battery.unwrap_or(false) {
+        providers.push(BatteryProvider::new(host_to_device_sender.clone()));
+    }
+
+    if let Some(calendar_config) = &config::get_config().calendar {
+        providers.push(CalendarProvider::new(host_to_device_sender.clone(), calendar_config.clone()));
+    }
+
+    if let Some(timer_config) = &config::get_config().timer {
+        providers.push(TimerProvider::new(
+            host_to_device_sender.clone(),
+            device_to_host_sender.clone(),
+            timer_config.clone(),
+        ));
+    }
+
+    if let Some(sun_config) = &config::get_config().sun {
+        providers.push(SunProvider::new(host_to_device_sender.clone(), sun_config.clone()));
+    }
+
+    for command_config in config::get_config().commands.iter().flatten() {
+        providers.push(CommandProvider::new(host_to_device_sender.clone(), command_config.clone()));
+    }
+
+    for file_config in config::get_config().files.iter().flatten() {
+        providers.push(FileProvider::new(host_to_device_sender.clone(), file_config.clone()));
+    }
+
+    return providers;
 }
 
 #[cfg(any(target_os = "linux", target_os = "macos"))]
@@ -92,12 +185,16 @@ fn get_providers(
     device_to_host_sender: &broadcast::Sender<Vec<u8>>,
 ) -> Vec<Box<dyn Provider>> {
     #[cfg(target_os = "linux")]
-    let media_provider = MediaProvider::new(host_to_device_sender.clone(), config::get_config().extended_media.unwrap_or(false));
+    let media_provider = MediaProvider::new(
+        host_to_device_sender.clone(),
+        device_to_host_sender.clone(),
+        config::get_config().extended_media.unwrap_or(false),
+    );
     #[cfg(target_os = "macos")]
     let media_provider = MediaProvider::new(host_to_device_sender.clone());
 
     let mut providers: Vec<Box<dyn Provider>> = vec![
-        TimeProvider::new(host_to_device_sender.clone()),
+        TimeProvider::new(host_to_device_sender.clone(), config::get_config().time.clone().unwrap_or_default()),
         VolumeProvider::new(host_to_device_sender.clone()),
         LayoutProvider::new(host_to_device_sender.clone()),
         media_provider,
@@ -108,6 +205,65 @@ fn get_providers(
         providers.push(WeatherProvider::new(host_to_device_sender.clone(), weather_config.url.clone()));
     }
 
+    if config::get_config().battery.unwrap_or(false) {
+        providers.push(BatteryProvider::new(host_to_device_sender.clone()));
+    }
+
+    if let Some(calendar_config) = &config::get_config().calendar {
+        providers.push(CalendarProvider::new(host_to_device_sender.clone(), calendar_config.clone()));
+    }
+
+    if let Some(timer_config) = &config::get_config().timer {
+        providers.push(TimerProvider::new(
+            host_to_device_sender.clone(),
+            device_to_host_sender.clone(),
+            timer_config.clone(),
+        ));
+    }
+
+    if let Some(sun_config) = &config::get_config().sun {
+        providers.push(SunProvider::new(host_to_device_sender.clone(), sun_config.clone()));
+    }
+
+    for command_config in config::get_config().commands.iter().flatten() {
+        providers.push(CommandProvider::new(host_to_device_sender.clone(), command_config.clone()));
+    }
+
+    for file_config in config::get_config().files.iter().flatten() {
+        providers.push(FileProvider::new(host_to_device_sender.clone(), file_config.clone()));
+    }
+
+    #[cfg(target_os = "linux")]
+    if let Some(network_config) = &config::get_config().network {
+        providers.push(NetworkProvider::new(host_to_device_sender.clone(), network_config.clone()));
+    }
+
+    #[cfg(target_os = "linux")]
+    if let Some(audio_devices) = &config::get_config().audio_devices {
+        providers.push(AudioDeviceProvider::new(
+            host_to_device_sender.clone(),
+            device_to_host_sender.clone(),
+            audio_devices.clone(),
+        ));
+    }
+
+    #[cfg(target_os = "linux")]
+    if config::get_config().focused_apps.is_some() || config::get_config().app_layers.is_some() {
+        providers.push(FocusedAppProvider::new(
+            host_to_device_sender.clone(),
+            config::get_config().focused_apps.clone(),
+            config::get_config().app_layers.clone(),
+        ));
+    }
+
+    #[cfg(target_os = "linux")]
+    if let Some(notifications_config) = &config::get_config().notifications {
+        providers.push(NotificationsProvider::new(
+            host_to_device_sender.clone(),
+            notifications_config.clone(),
+        ));
+    }
+
     return providers;
 }
 
@@ -134,34 +290,86 @@ fn run(
     }
 }
 
+/// Providers which keep running while the session is locked, other providers can be paused by them
+#[cfg_attr(not(target_os = "linux"), allow(unused_variables))]
+fn get_session_providers(
+    host_to_device_sender: &broadcast::Sender<Vec<u8>>,
+    is_locked_sender: mpsc::Sender<bool>,
+) -> Vec<Box<dyn Provider>> {
+    #[allow(unused_mut)]
+    let mut providers: Vec<Box<dyn Provider>> = vec![];
+
+    #[cfg(target_os = "linux")]
+    if let Some(session_state_config) = &config::get_config().session_state {
+        let is_locked_sender = session_state_config.pause_providers.unwrap_or(false).then_some(is_locked_sender);
+        providers.push(SessionStateProvider::new(host_to_device_sender.clone(), is_locked_sender));
+    }
+
+    return providers;
+}
+
+/// Starts or stops providers, returns new state
+fn update_providers(providers: &[Box<dyn Provider>], is_started: bool, should_start: bool, should_restart: bool) -> bool {
+    if providers.is_empty() {
+        return is_started;
+    }
+
+    let mut is_started = is_started;
+    if is_started && (!should_start || should_restart) {
+        tracing::info!("Stopping providers");
+        is_started = false;
+        providers.iter().for_each(|p| p.stop());
+        std::thread::sleep(std::time::Duration::from_millis(200));
+    }
+
+    if !is_started && should_start {
+        tracing::info!("Starting providers");
+        is_started = true;
+        providers.iter().for_each(|p| p.start());
+    }
+
+    return is_started;
+}
+
 fn start(
     host_to_device_sender: broadcast::Sender<Vec<u8>>,
     device_to_host_sender: broadcast::Sender<Vec<u8>>,
     mut is_connected_receiver: mpsc::Receiver<bool>,
 ) {
+    let (is_locked_sender, mut is_locked_receiver) = mpsc::channel::<bool>(1);
     let providers = get_providers(&host_to_device_sender, &device_to_host_sender);
+    let session_providers = get_session_providers(&host_to_device_sender, is_locked_sender);
 
     let mut connected_count = 0;
+    let mut is_locked = false;
     let mut is_started = false;
+    let mut is_session_started = false;
 
-    loop {
-        if let Some(is_connected) = is_connected_receiver.blocking_recv() {
-            connected_count += if is_connected { 1 } else { -1 };
-            tracing::info!("Connected devices: {}", connected_count);
+    // providers are synchronous, runtime is only used to wait for both channels at once
+    let runtime = match tokio::runtime::Builder::new_current_thread().build() {
+        Ok(runtime) => runtime,
+        Err(e) => return tracing::error!("can not create runtime: {}", e),
+    };
 
-            // if new device is connected - restart providers to send all available data
-            if is_started && (connected_count == 0 || is_connected) {
-                tracing::info!("Stopping providers");
-                is_started = false;
-                providers.iter().for_each(|p| p.stop());
-                std::thread::sleep(std::time::Duration::from_millis(200));
+    runtime.block_on(async {
+        loop {
+            let mut is_new_device = false;
+            tokio::select! {
+                Some(is_connected) = is_connected_receiver.recv() => {
+                    connected_count += if is_connected { 1 } else { -1 };
+                    tracing::info!("Connected devices: {}", connected_count);
+                    is_new_device = is_connected;
+                }
+                Some(value) = is_locked_receiver.recv() => {
+                    tracing::info!("Session is {}", if value { "locked" } else { "unlocked" });
+                    is_locked = value;
+                }
+                else => break,
             }
 
-            if !is_started && connected_count > 0 {
-                tracing::info!("Starting providers");
-                is_started = true;
-                providers.iter().for_each(|p| p.start());
-            }
+            // if new device is connected - restart providers to send all available data
+            is_session_started = update_providers(&session_providers, is_session_started, connected_count > 0, is_new_device);
+            is_started = update_providers(&providers, is_started, connected_count > 0 && !is_locked, is_new_device);
         }
-    }
+    });
 }
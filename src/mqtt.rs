@@ -0,0 +1,311 @@
+mod packet;
+
+use std::io::{self, Write};
+use std::net::TcpStream;
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::config::{MqttConfig, MqttSubscription};
+use crate::data_type::DataType;
+use crate::keyboard::{self, Keyboard};
+use crate::utils::value_parser;
+
+const KEEP_ALIVE_SECONDS: u16 = 60;
+const RECONNECT_DELAY_SECONDS: u64 = 5;
+
+struct Connection {
+    stream: TcpStream,
+    /// Packets are read in another thread, as reading is blocking
+    packet_receiver: mpsc::Receiver<(u8, u8, Vec<u8>)>,
+}
+
+fn connect(config: &MqttConfig, prefix: &str) -> io::Result<Connection> {
+    let mut stream = TcpStream::connect((config.host.as_str(), config.port.unwrap_or(1883)))?;
+    let client_id = config.client_id.clone().unwrap_or("qmk-hid-host".to_string());
+    let status_topic = format!("{}/status", prefix);
+    stream.write_all(&packet::connect(
+        &client_id,
+        config.username.as_deref(),
+        config.password.as_deref(),
+        (&status_topic, "offline"),
+        KEEP_ALIVE_SECONDS,
+    ))?;
+
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    match packet::read(&mut stream)? {
+        (packet::CONNACK, _, body) if body.get(1) == Some(&0) => {}
+        (packet::CONNACK, _, body) => {
+            return Err(io::Error::other(format!("connection is refused with code {:?}", body.get(1))));
+        }
+        _ => return Err(io::Error::other("unexpected packet instead of CONNACK")),
+    }
+    stream.set_read_timeout(None)?;
+
+    let (packet_sender, packet_receiver) = mpsc::channel();
+    let mut reader = stream.try_clone()?;
+    std::thread::spawn(move || {
+        while let Ok(packet) = packet::read(&mut reader) {
+            if packet_sender.send(packet).is_err() {
+                break;
+            }
+        }
+    });
+
+    let topics = config.subscriptions.iter().flatten().map(|x| x.topic.as_str()).collect::<Vec<_>>();
+    if !topics.is_empty() {
+        stream.write_all(&packet::subscribe(1, &topics))?;
+    }
+    stream.write_all(&packet::publish(&status_topic, b"online", true))?;
+
+    return Ok(Connection { stream, packet_receiver });
+}
+
+/// Payload is parsed the same way as command output, frame starts with configured data type
+fn get_frame(subscription: &MqttSubscription, payload: &[u8]) -> Option<Vec<u8>> {
+    let payload = String::from_utf8_lossy(payload);
+    let mut data = value_parser::parse(&payload, subscription.parser.unwrap_or_default(), subscription.json_path.as_deref())?;
+    data.insert(0, subscription.data_type);
+    return Some(data);
+}
+
+/// Frame is sent to all devices if there is no name, errors are only logged as the broker does not expect any reply
+fn send_frame(
+    frame: &[u8],
+    device_name: Option<&str>,
+    keyboards: &[Keyboard],
+    host_to_device_sender: &broadcast::Sender<Vec<u8>>,
+    device_frame_sender: &broadcast::Sender<(String, Vec<u8>)>,
+) {
+    if let Err(e) = keyboard::send_frame(frame.to_vec(), device_name, keyboards, host_to_device_sender, device_frame_sender) {
+        tracing::error!("MQTT: can not send {:?} to {}: {:?}", frame, device_name.unwrap_or("devices"), e);
+    }
+}
+
+/// Returns parsed frames of all subscriptions matching the topic
+fn handle_publish(flags: u8, body: &[u8], subscriptions: &[MqttSubscription]) -> Vec<(usize, Vec<u8>)> {
+    let Some((topic, payload)) = packet::parse_publish(flags, body) else {
+        return vec![];
+    };
+
+    let mut frames = vec![];
+    for (index, subscription) in subscriptions.iter().enumerate() {
+        if !packet::is_topic_match(&subscription.topic, &topic) {
+            continue;
+        }
+
+        match get_frame(subscription, &payload) {
+            Some(frame) => {
+                tracing::info!("MQTT: '{}' received {:?}", topic, frame);
+                frames.push((index, frame));
+            }
+            None => tracing::error!("MQTT: can not parse '{}' payload: {}", topic, String::from_utf8_lossy(&payload)),
+        }
+    }
+    return frames;
+}
+
+/// Returns when connection is lost or the broker does not answer pings
+fn run(
+    connection: &mut Connection,
+    config: &MqttConfig,
+    prefix: &str,
+    keyboards: &[Keyboard],
+    host_to_device_sender: &broadcast::Sender<Vec<u8>>,
+    device_frame_sender: &broadcast::Sender<(String, Vec<u8>)>,
+    relay_receiver: &mpsc::Receiver<(String, Vec<u8>)>,
+) -> io::Result<()> {
+    let subscriptions = config.subscriptions.clone().unwrap_or_default();
+    let mut frames: Vec<Option<Vec<u8>>> = vec![None; subscriptions.len()];
+    let mut connected_states: Vec<Option<bool>> = vec![None; keyboards.len()];
+    let mut pinged_at = Instant::now();
+    let mut received_at = Instant::now();
+
+    // relay frames received while disconnected are outdated
+    while relay_receiver.try_recv().is_ok() {}
+
+    loop {
+        for (keyboard, connected_state) in keyboards.iter().zip(connected_states.iter_mut()) {
+            let is_connected = keyboard.is_connected();
+            if *connected_state == Some(is_connected) {
+                continue;
+            }
+
+            let topic = format!("{}/devices/{}/connected", prefix, keyboard.get_name());
+            connection
+                .stream
+                .write_all(&packet::publish(&topic, is_connected.to_string().as_bytes(), true))?;
+            // only the connected device gets the last frames again, other devices already have them
+            if is_connected && connected_state.is_some() {
+                for (frame, subscription) in frames.iter().zip(&subscriptions) {
+                    let is_device_frame = subscription.device.as_deref().is_none_or(|x| x == keyboard.get_name());
+                    if let (Some(frame), true) = (frame, is_device_frame) {
+                        send_frame(
+                            frame,
+                            Some(keyboard.get_name()),
+                            keyboards,
+                            host_to_device_sender,
+                            device_frame_sender,
+                        );
+                    }
+                }
+            }
+            *connected_state = Some(is_connected);
+        }
+
+        match connection.packet_receiver.recv_timeout(Duration::from_millis(100)) {
+            Ok((packet_type, flags, body)) => {
+                received_at = Instant::now();
+                if packet_type == packet::PUBLISH {
+                    for (index, frame) in handle_publish(flags, &body, &subscriptions) {
+                        let device_name = subscriptions[index].device.as_deref();
+                        send_frame(&frame, device_name, keyboards, host_to_device_sender, device_frame_sender);
+                        frames[index] = Some(frame);
+                    }
+                }
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => return Err(io::Error::other("connection is closed")),
+        }
+
+        // relay frames are published as hex string without data type, e.g. `0102ff...`
+        while let Ok((device_name, data)) = relay_receiver.try_recv() {
+            let payload = data[1..].iter().map(|x| format!("{:02x}", x)).collect::<String>();
+            let topic = format!("{}/relay/{}", prefix, device_name);
+            connection.stream.write_all(&packet::publish(&topic, payload.as_bytes(), false))?;
+        }
+
+        // broker answers each ping, so silence means the connection is lost without being closed
+        if received_at.elapsed() >= Duration::from_millis(KEEP_ALIVE_SECONDS as u64 * 1500) {
+            return Err(io::Error::new(io::ErrorKind::TimedOut, "broker does not respond"));
+        }
+
+        if pinged_at.elapsed() >= Duration::from_secs(KEEP_ALIVE_SECONDS as u64 / 2) {
+            pinged_at = Instant::now();
+            connection.stream.write_all(&packet::PINGREQ)?;
+        }
+    }
+}
+
+/// Relay frames are received in another thread, so none of them are missed while waiting for the broker
+fn start_relay(named_device_to_host_sender: &broadcast::Sender<(String, Vec<u8>)>) -> mpsc::Receiver<(String, Vec<u8>)> {
+    let mut named_device_to_host_receiver = named_device_to_host_sender.subscribe();
+    let (relay_sender, relay_receiver) = mpsc::channel();
+    std::thread::spawn(move || loop {
+        match named_device_to_host_receiver.blocking_recv() {
+            Ok((device_name, data)) if data.first() == Some(&(DataType::RelayFromDevice as u8)) => {
+                if relay_sender.send((device_name, data)).is_err() {
+                    break;
+                }
+            }
+            Ok(_) => {}
+            Err(RecvError::Lagged(count)) => tracing::warn!("MQTT: {} frames from devices were skipped", count),
+            Err(RecvError::Closed) => break,
+        }
+    });
+    return relay_receiver;
+}
+
+/// Bridge keeps running and reconnecting to the broker regardless of connected devices
+pub fn start(
+    config: MqttConfig,
+    keyboards: Arc<Vec<Keyboard>>,
+    host_to_device_sender: broadcast::Sender<Vec<u8>>,
+    device_frame_sender: broadcast::Sender<(String, Vec<u8>)>,
+    named_device_to_host_sender: broadcast::Sender<(String, Vec<u8>)>,
+) {
+    let prefix = config.topic_prefix.clone().unwrap_or("qmk-hid-host".to_string());
+    let relay_receiver = start_relay(&named_device_to_host_sender);
+    std::thread::spawn(move || loop {
+        match connect(&config, &prefix) {
+            Ok(mut connection) => {
+                tracing::info!("MQTT: connected to {}", config.host);
+                if let Err(e) = run(
+                    &mut connection,
+                    &config,
+                    &prefix,
+                    &keyboards,
+                    &host_to_device_sender,
+                    &device_frame_sender,
+                    &relay_receiver,
+                ) {
+                    tracing::error!("MQTT: disconnected from {}: {}", config.host, e);
+                }
+                let _ = connection.stream.shutdown(std::net::Shutdown::Both);
+            }
+            Err(e) => tracing::error!("MQTT: can not connect to {}: {}", config.host, e),
+        }
+
+        std::thread::sleep(Duration::from_secs(RECONNECT_DELAY_SECONDS));
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    fn read_publish(stream: &mut TcpStream) -> (String, String) {
+        let (packet_type, flags, body) = packet::read(stream).unwrap();
+        assert_eq!(packet_type, packet::PUBLISH);
+        let (topic, payload) = packet::parse_publish(flags, &body).unwrap();
+        return (topic, String::from_utf8(payload).unwrap());
+    }
+
+    /// Broker is a listener on a random port, which checks packets sent by the bridge and closes the connection at the end
+    #[test]
+    fn bridges_broker_and_devices() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let config = serde_json::from_value::<MqttConfig>(serde_json::json!({
+            "host": "127.0.0.1",
+            "port": listener.local_addr().unwrap().port(),
+            "subscriptions": [{ "topic": "home/+/temp", "dataType": 0x40, "parser": "integer" }],
+        }))
+        .unwrap();
+        let device = serde_json::from_str(r#"{ "name": "left", "productId": "0x0844" }"#).unwrap();
+        let keyboards = vec![Keyboard::new(&device, 5000)];
+        let (host_to_device_sender, mut host_to_device_receiver) = broadcast::channel(1);
+        let (device_frame_sender, _) = broadcast::channel(1);
+        let (relay_sender, relay_receiver) = mpsc::channel();
+
+        let broker = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let (packet_type, _, body) = packet::read(&mut stream).unwrap();
+            assert_eq!(packet_type, 1);
+            assert!(body.ends_with(b"qmk-hid-host/status\0\x07offline"));
+            stream.write_all(&[0x20, 2, 0, 0]).unwrap();
+
+            assert_eq!(packet::read(&mut stream).unwrap(), (8, 2, b"\0\x01\0\x0bhome/+/temp\0".to_vec()));
+            assert_eq!(read_publish(&mut stream), ("qmk-hid-host/status".to_string(), "online".to_string()));
+            // relay frame is sent after the bridge started running, as frames received before are dropped
+            assert_eq!(
+                read_publish(&mut stream),
+                ("qmk-hid-host/devices/left/connected".to_string(), "false".to_string())
+            );
+
+            stream.write_all(&packet::publish("home/kitchen/temp", b"21.6", false)).unwrap();
+            relay_sender
+                .send(("left".to_string(), vec![DataType::RelayFromDevice as u8, 1, 0xff]))
+                .unwrap();
+            assert_eq!(
+                read_publish(&mut stream),
+                ("qmk-hid-host/relay/left".to_string(), "01ff".to_string())
+            );
+        });
+
+        let mut connection = connect(&config, "qmk-hid-host").unwrap();
+        let result = run(
+            &mut connection,
+            &config,
+            "qmk-hid-host",
+            &keyboards,
+            &host_to_device_sender,
+            &device_frame_sender,
+            &relay_receiver,
+        );
+        broker.join().unwrap();
+
+        assert_eq!(result.unwrap_err().to_string(), "connection is closed");
+        assert_eq!(host_to_device_receiver.try_recv().unwrap(), vec![0x40, 22, 0, 0, 0]);
+    }
+}
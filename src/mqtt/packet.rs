@@ -0,0 +1,176 @@
+use std::io::{self, Read};
+
+pub const CONNACK: u8 = 2;
+pub const PUBLISH: u8 = 3;
+
+pub const PINGREQ: [u8; 2] = [0xC0, 0];
+
+fn encode_packet(header: u8, body: &[u8]) -> Vec<u8> {
+    let mut packet = vec![header];
+    let mut length = body.len();
+    loop {
+        let mut byte = (length % 128) as u8;
+        length /= 128;
+        if length > 0 {
+            byte |= 0x80;
+        }
+        packet.push(byte);
+        if length == 0 {
+            break;
+        }
+    }
+    packet.extend_from_slice(body);
+    return packet;
+}
+
+fn encode_string(value: &str, body: &mut Vec<u8>) {
+    body.extend_from_slice(&(value.len() as u16).to_be_bytes());
+    body.extend_from_slice(value.as_bytes());
+}
+
+/// MQTT 3.1.1 connect with clean session and retained last will, which is published by the broker when connection is lost
+pub fn connect(client_id: &str, username: Option<&str>, password: Option<&str>, will: (&str, &str), keep_alive: u16) -> Vec<u8> {
+    let mut flags = 0x02 | 0x04 | 0x20;
+    if username.is_some() {
+        flags |= 0x80;
+    }
+    if password.is_some() {
+        flags |= 0x40;
+    }
+
+    let mut body = vec![];
+    encode_string("MQTT", &mut body);
+    body.extend_from_slice(&[4, flags]);
+    body.extend_from_slice(&keep_alive.to_be_bytes());
+    encode_string(client_id, &mut body);
+    encode_string(will.0, &mut body);
+    encode_string(will.1, &mut body);
+    for value in [username, password].into_iter().flatten() {
+        encode_string(value, &mut body);
+    }
+    return encode_packet(0x10, &body);
+}
+
+/// All topics are subscribed with QoS 0
+pub fn subscribe(packet_id: u16, topics: &[&str]) -> Vec<u8> {
+    let mut body = packet_id.to_be_bytes().to_vec();
+    for topic in topics {
+        encode_string(topic, &mut body);
+        body.push(0);
+    }
+    return encode_packet(0x82, &body);
+}
+
+/// Messages are published with QoS 0
+pub fn publish(topic: &str, payload: &[u8], retain: bool) -> Vec<u8> {
+    let mut body = vec![];
+    encode_string(topic, &mut body);
+    body.extend_from_slice(payload);
+    return encode_packet(0x30 | retain as u8, &body);
+}
+
+/// Returns packet type, flags and body
+pub fn read(stream: &mut impl Read) -> io::Result<(u8, u8, Vec<u8>)> {
+    let mut byte = [0u8; 1];
+    stream.read_exact(&mut byte)?;
+    let header = byte[0];
+
+    let mut length = 0usize;
+    for index in 0..4 {
+        stream.read_exact(&mut byte)?;
+        length |= ((byte[0] & 0x7F) as usize) << (7 * index);
+        if byte[0] & 0x80 == 0 {
+            break;
+        }
+    }
+
+    let mut body = vec![0u8; length];
+    stream.read_exact(&mut body)?;
+    return Ok((header >> 4, header & 0x0F, body));
+}
+
+/// Returns topic and payload
+pub fn parse_publish(flags: u8, body: &[u8]) -> Option<(String, Vec<u8>)> {
+    let topic_length = u16::from_be_bytes([*body.first()?, *body.get(1)?]) as usize;
+    let topic = String::from_utf8(body.get(2..2 + topic_length)?.to_vec()).ok()?;
+    // packet id is present only for QoS 1 and 2
+    let payload_start = 2 + topic_length + if (flags >> 1) & 3 > 0 { 2 } else { 0 };
+    return Some((topic, body.get(payload_start..)?.to_vec()));
+}
+
+/// Filter can contain `+` for a single level and `#` for all remaining levels
+pub fn is_topic_match(filter: &str, topic: &str) -> bool {
+    let mut topic_levels = topic.split('/');
+    for level in filter.split('/') {
+        match (level, topic_levels.next()) {
+            ("#", _) => return true,
+            ("+", Some(_)) => {}
+            (level, Some(topic_level)) if level == topic_level => {}
+            _ => return false,
+        }
+    }
+    return topic_levels.next().is_none();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_remaining_length() {
+        for (length, encoded) in [
+            (0, vec![0]),
+            (127, vec![0x7F]),
+            (128, vec![0x80, 0x01]),
+            (16383, vec![0xFF, 0x7F]),
+            (16384, vec![0x80, 0x80, 0x01]),
+        ] {
+            let packet = encode_packet(0x30, &vec![0xAB; length]);
+            assert_eq!(packet[1..1 + encoded.len()], encoded, "length {}", length);
+            assert_eq!(packet.len(), 1 + encoded.len() + length);
+            assert_eq!(read(&mut packet.as_slice()).unwrap(), (3, 0, vec![0xAB; length]));
+        }
+    }
+
+    #[test]
+    fn encodes_connect() {
+        let packet = connect("id", None, None, ("s", "off"), 60);
+        let mut expected = vec![0x10, 22, 0, 4];
+        expected.extend_from_slice(b"MQTT");
+        expected.extend_from_slice(&[4, 0x26, 0, 60, 0, 2, b'i', b'd', 0, 1, b's', 0, 3, b'o', b'f', b'f']);
+        assert_eq!(packet, expected);
+
+        let packet = connect("id", Some("u"), Some("p"), ("s", "off"), 60);
+        assert_eq!(packet[9], 0x26 | 0x80 | 0x40);
+        assert!(packet.ends_with(&[0, 1, b'u', 0, 1, b'p']));
+    }
+
+    #[test]
+    fn parses_publish() {
+        let (_, flags, body) = read(&mut publish("a/b", b"42", false).as_slice()).unwrap();
+        assert_eq!(flags, 0);
+        assert_eq!(parse_publish(flags, &body), Some(("a/b".to_string(), b"42".to_vec())));
+
+        // QoS 1 packet has id after the topic
+        let body = [0, 3, b'a', b'/', b'b', 0, 7, b'4', b'2'];
+        assert_eq!(parse_publish(0x02, &body), Some(("a/b".to_string(), b"42".to_vec())));
+        assert_eq!(parse_publish(0x04, &body), Some(("a/b".to_string(), b"42".to_vec())));
+        assert_eq!(parse_publish(0, &body[..3]), None);
+        assert_eq!(parse_publish(0x02, &body[..6]), None);
+    }
+
+    #[test]
+    fn matches_topics() {
+        assert!(is_topic_match("home/temp", "home/temp"));
+        assert!(!is_topic_match("home/temp", "home/humidity"));
+        assert!(is_topic_match("home/+/temp", "home/kitchen/temp"));
+        assert!(!is_topic_match("home/+/temp", "home/kitchen/floor/temp"));
+        assert!(!is_topic_match("home/+", "home"));
+        assert!(is_topic_match("home/#", "home/kitchen/temp"));
+        assert!(is_topic_match("#", "home"));
+        // trailing levels of the topic or the filter do not match
+        assert!(!is_topic_match("home", "home/temp"));
+        assert!(!is_topic_match("home/temp", "home"));
+        assert!(!is_topic_match("home/temp", "home/temp/"));
+    }
+}
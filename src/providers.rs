@@ -1,7 +1,18 @@
 pub mod _base;
+pub mod audio_device;
+pub mod battery;
+pub mod calendar;
+pub mod command;
+pub mod file;
+pub mod focused_app;
 pub mod layout;
 pub mod media;
+pub mod network;
+pub mod notifications;
 pub mod relay;
+pub mod session_state;
+pub mod sun;
 pub mod time;
+pub mod timer;
 pub mod volume;
 pub mod weather;
@@ -0,0 +1,208 @@
+#![cfg(target_os = "linux")]
+use libpulse_binding::context::subscribe::Facility;
+use pulsectl::controllers::types::DeviceInfo;
+use pulsectl::controllers::{DeviceControl, SinkController};
+use std::ops::Deref;
+use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{broadcast, oneshot};
+
+use crate::data_type::DataType;
+use crate::utils::command_receiver::{CommandReceiver, Received};
+
+use super::_base::Provider;
+
+const COMMAND_NEXT: u8 = 0;
+const COMMAND_PREVIOUS: u8 = 1;
+const COMMAND_SELECT_INDEX: u8 = 2;
+const COMMAND_SELECT_NAME: u8 = 3;
+
+fn get_display_name(device: &DeviceInfo) -> String {
+    return device.description.clone().or(device.name.clone()).unwrap_or_default();
+}
+
+/// Configured devices can be matched by either sink name or description.
+fn is_device(device: &DeviceInfo, name: &str) -> bool {
+    return device.name.as_deref() == Some(name) || device.description.as_deref() == Some(name);
+}
+
+fn get_device_index(device: &DeviceInfo, devices: &[String]) -> Option<usize> {
+    return devices.iter().position(|x| is_device(device, x));
+}
+
+fn get_default_device() -> Option<DeviceInfo> {
+    let mut controller = SinkController::create().ok()?;
+    return controller.get_default_device().ok();
+}
+
+/// Cuts the name at a char boundary, so multi-byte characters are not split
+fn truncate_name(name: &str, max_bytes: usize) -> &str {
+    if name.len() <= max_bytes {
+        return name;
+    }
+
+    let mut boundary = max_bytes;
+    while !name.is_char_boundary(boundary) {
+        boundary -= 1;
+    }
+    return &name[..boundary];
+}
+
+fn send_data(device: &DeviceInfo, devices: &[String], data_sender: &broadcast::Sender<Vec<u8>>) {
+    let name = get_display_name(device);
+    let index = get_device_index(device, devices).map(|x| x as u8).unwrap_or(u8::MAX);
+    tracing::info!("new audio device: '{0}', device list: {1:?}", name, devices);
+
+    let mut data = truncate_name(&name, 29).as_bytes().to_vec();
+    data.insert(0, data.len() as u8);
+    data.insert(0, index);
+    data.insert(0, DataType::AudioDevice as u8);
+    if let Err(e) = data_sender.send(data) {
+        tracing::error!("Audio Device Provider failed to send data: {:?}", e);
+    }
+}
+
+fn switch_device(command: &[u8], devices: &[String]) {
+    let mut controller = match SinkController::create() {
+        Ok(controller) => controller,
+        Err(e) => return tracing::error!("Audio Device Provider failed to connect: {}", e),
+    };
+
+    // only configured devices which are currently available can be selected
+    let available = controller.list_devices().unwrap_or_default();
+    let available = devices
+        .iter()
+        .filter_map(|x| available.iter().find(|device| is_device(device, x)))
+        .collect::<Vec<_>>();
+    if available.is_empty() {
+        return tracing::warn!("Audio Device Provider: none of configured devices {:?} is available", devices);
+    }
+
+    let current = controller
+        .get_default_device()
+        .ok()
+        .and_then(|default| available.iter().position(|x| x.name == default.name));
+
+    let target = match command.first() {
+        Some(&COMMAND_NEXT) => current.map(|x| (x + 1) % available.len()).or(Some(0)),
+        Some(&COMMAND_PREVIOUS) => current.map(|x| (x + available.len() - 1) % available.len()).or(Some(0)),
+        Some(&COMMAND_SELECT_INDEX) => command
+            .get(1)
+            .and_then(|index| devices.get(*index as usize))
+            .and_then(|name| available.iter().position(|x| is_device(x, name))),
+        Some(&COMMAND_SELECT_NAME) => {
+            let length = *command.get(1).unwrap_or(&0) as usize;
+            let name = String::from_utf8_lossy(command.get(2..2 + length).unwrap_or_default()).to_string();
+            available.iter().position(|x| is_device(x, &name))
+        }
+        _ => None,
+    };
+
+    if let Some(name) = target.and_then(|x| available[x].name.clone()) {
+        tracing::info!("switching audio device to '{}'", name);
+        if let Err(e) = controller.set_default_device(&name) {
+            tracing::error!("Audio Device Provider failed to switch device: {}", e);
+        }
+    }
+}
+
+pub struct AudioDeviceProvider {
+    host_to_device_sender: broadcast::Sender<Vec<u8>>,
+    device_to_host_sender: broadcast::Sender<Vec<u8>>,
+    is_started: Arc<AtomicBool>,
+    devices: Vec<String>,
+    /// Dropped on stop to wake up the command thread
+    command_stop_sender: Mutex<Option<oneshot::Sender<()>>>,
+}
+
+impl AudioDeviceProvider {
+    pub fn new(
+        host_to_device_sender: broadcast::Sender<Vec<u8>>,
+        device_to_host_sender: broadcast::Sender<Vec<u8>>,
+        devices: Vec<String>,
+    ) -> Box<dyn Provider> {
+        let provider = AudioDeviceProvider {
+            host_to_device_sender,
+            device_to_host_sender,
+            is_started: Arc::new(AtomicBool::new(false)),
+            devices,
+            command_stop_sender: Mutex::new(None),
+        };
+        return Box::new(provider);
+    }
+}
+
+impl Provider for AudioDeviceProvider {
+    fn start(&self) {
+        tracing::info!("Audio Device Provider started");
+        self.is_started.store(true, Relaxed);
+        let data_sender = self.host_to_device_sender.clone();
+        let is_started = self.is_started.clone();
+        let devices = self.devices.clone();
+
+        let default_device = get_default_device();
+        let mut synced_device = default_device.as_ref().and_then(|x| x.name.clone());
+        if let Some(device) = &default_device {
+            send_data(device, &devices, &data_sender);
+        }
+
+        std::thread::spawn(move || {
+            let controller = SinkController::create().map_err(|e| tracing::error!("{}", e)).unwrap();
+            let mut ctx = controller.handler.context.deref().borrow_mut();
+
+            // default sink changes are reported as server events
+            ctx.set_subscribe_callback(Some(Box::new(move |_, _, _| {
+                if let Some(device) = get_default_device() {
+                    if synced_device != device.name {
+                        synced_device = device.name.clone();
+                        send_data(&device, &devices, &data_sender);
+                    }
+                }
+            })));
+
+            ctx.subscribe(Facility::Server.to_interest_mask(), |_| {});
+
+            loop {
+                if !is_started.load(Relaxed) {
+                    break;
+                }
+
+                controller.handler.mainloop.deref().borrow_mut().iterate(true);
+            }
+
+            tracing::info!("Audio Device Provider stopped");
+        });
+
+        let devices = self.devices.clone();
+        let (mut command_receiver, command_stop_sender) =
+            match CommandReceiver::new(&self.device_to_host_sender, DataType::AudioDeviceCommand) {
+                Ok(command_receiver) => command_receiver,
+                Err(e) => return tracing::error!("Audio Device Provider can not receive commands: {}", e),
+            };
+        *self.command_stop_sender.lock().unwrap() = Some(command_stop_sender);
+        std::thread::spawn(move || {
+            while let Received::Command(command) = command_receiver.recv(None) {
+                switch_device(&command, &devices);
+            }
+        });
+    }
+
+    fn stop(&self) {
+        self.is_started.store(false, Relaxed);
+        self.command_stop_sender.lock().unwrap().take();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncates_name_at_char_boundary() {
+        assert_eq!(truncate_name("Speakers", 29), "Speakers");
+        // 'ö' takes 2 bytes, it is dropped instead of being split
+        assert_eq!(truncate_name("Kopfhörer", 5), "Kopfh");
+        assert_eq!(truncate_name("Kopfhörer", 6), "Kopfh");
+        assert_eq!(truncate_name("Kopfhörer", 7), "Kopfhö");
+    }
+}
@@ -0,0 +1,129 @@
+#[cfg(target_os = "linux")]
+mod linux;
+
+#[cfg(target_os = "linux")]
+use self::linux::{get_battery_state, PowerEvents};
+
+#[cfg(target_os = "windows")]
+mod windows;
+
+#[cfg(target_os = "windows")]
+use self::windows::{get_battery_state, PowerEvents};
+
+#[cfg(target_os = "macos")]
+mod macos;
+
+#[cfg(target_os = "macos")]
+use self::macos::{get_battery_state, PowerEvents};
+
+use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+
+use crate::data_type::DataType;
+
+use super::_base::Provider;
+
+/// Charge level changes slowly, so it is polled, while power source changes are pushed as soon as they happen
+const POLL_INTERVAL_SECONDS: u64 = 30;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BatteryStatus {
+    Discharging = 0,
+    Charging = 1,
+    Full = 2,
+    NotCharging = 3,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BatteryState {
+    pub percentage: u8,
+    pub status: BatteryStatus,
+    pub ac_online: bool,
+    /// minutes until empty (discharging) or full (charging)
+    pub time_remaining: Option<u16>,
+}
+
+fn send_data(value: &BatteryState, host_to_device_sender: &broadcast::Sender<Vec<u8>>) {
+    tracing::info!("new battery state: {:?}", value);
+    let time_remaining = value.time_remaining.unwrap_or(u16::MAX);
+    let mut data = vec![DataType::Battery as u8, value.percentage, value.status as u8, value.ac_online as u8];
+    data.extend_from_slice(&time_remaining.to_le_bytes());
+    if let Err(e) = host_to_device_sender.send(data) {
+        tracing::error!("Battery Provider failed to send data: {:?}", e);
+    }
+}
+
+pub struct BatteryProvider {
+    host_to_device_sender: broadcast::Sender<Vec<u8>>,
+    is_started: Arc<AtomicBool>,
+}
+
+impl BatteryProvider {
+    pub fn new(host_to_device_sender: broadcast::Sender<Vec<u8>>) -> Box<dyn Provider> {
+        let provider = BatteryProvider {
+            host_to_device_sender,
+            is_started: Arc::new(AtomicBool::new(false)),
+        };
+        return Box::new(provider);
+    }
+}
+
+impl Provider for BatteryProvider {
+    fn start(&self) {
+        tracing::info!("Battery Provider started");
+        self.is_started.store(true, Relaxed);
+        let host_to_device_sender = self.host_to_device_sender.clone();
+        let is_started = self.is_started.clone();
+        std::thread::spawn(move || {
+            let power_events = PowerEvents::new();
+            let mut is_power_changed = true;
+            let mut polled_at = Instant::now();
+            let mut synced_state: Option<BatteryState> = None;
+            let mut synced_at = Instant::now();
+            loop {
+                if !is_started.load(Relaxed) {
+                    break;
+                }
+
+                // time remaining alone fluctuates a lot, so it is resent at most once a minute
+                if is_power_changed || polled_at.elapsed().as_secs() >= POLL_INTERVAL_SECONDS {
+                    polled_at = Instant::now();
+                    if let Some(state) = get_battery_state() {
+                        let is_changed = match synced_state {
+                            Some(synced) => {
+                                synced.percentage != state.percentage
+                                    || synced.status != state.status
+                                    || synced.ac_online != state.ac_online
+                                    || (synced.time_remaining != state.time_remaining && synced_at.elapsed().as_secs() >= 60)
+                            }
+                            None => true,
+                        };
+
+                        if is_changed {
+                            synced_state = Some(state);
+                            synced_at = Instant::now();
+                            send_data(&state, &host_to_device_sender);
+                        }
+                    }
+                }
+
+                // stop is checked every second, without notifications the state is read every second
+                is_power_changed = match &power_events {
+                    Some(power_events) => power_events.wait(Duration::from_secs(1)),
+                    None => {
+                        std::thread::sleep(Duration::from_secs(1));
+                        true
+                    }
+                };
+            }
+
+            tracing::info!("Battery Provider stopped");
+        });
+    }
+
+    fn stop(&self) {
+        self.is_started.store(false, Relaxed);
+    }
+}
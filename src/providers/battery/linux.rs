@@ -0,0 +1,148 @@
+use std::fs;
+use std::io;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::path::Path;
+use std::time::Duration;
+
+use super::{BatteryState, BatteryStatus};
+
+const POWER_SUPPLY_PATH: &str = "/sys/class/power_supply";
+
+fn read_value(path: &Path, name: &str) -> Option<String> {
+    fs::read_to_string(path.join(name)).ok().map(|x| x.trim().to_string())
+}
+
+fn read_number(path: &Path, name: &str) -> Option<f64> {
+    read_value(path, name)?.parse::<f64>().ok()
+}
+
+fn get_status(value: &str) -> BatteryStatus {
+    match value {
+        "Charging" => BatteryStatus::Charging,
+        "Full" => BatteryStatus::Full,
+        "Not charging" => BatteryStatus::NotCharging,
+        _ => BatteryStatus::Discharging,
+    }
+}
+
+pub fn get_battery_state() -> Option<BatteryState> {
+    let mut ac_online = false;
+    let mut status: Option<BatteryStatus> = None;
+    let (mut now, mut full, mut rate) = (0.0, 0.0, 0.0);
+    let mut capacity: Option<u8> = None;
+
+    for entry in fs::read_dir(POWER_SUPPLY_PATH).ok()?.flatten() {
+        let path = entry.path();
+        match read_value(&path, "type").as_deref() {
+            Some("Mains") | Some("USB") => {
+                ac_online |= read_value(&path, "online").as_deref() == Some("1");
+            }
+            // peripheral batteries (mouse, headset) report scope "Device"
+            Some("Battery") if read_value(&path, "scope").as_deref() != Some("Device") => {
+                let battery_status = read_value(&path, "status")
+                    .map(|x| get_status(&x))
+                    .unwrap_or(BatteryStatus::Discharging);
+                if status.is_none() || battery_status != BatteryStatus::Full {
+                    status = Some(battery_status);
+                }
+
+                capacity = capacity.or(read_number(&path, "capacity").map(|x| x as u8));
+
+                // batteries report either energy (µWh) and power (µW) or charge (µAh) and current (µA)
+                if let (Some(energy_now), Some(energy_full)) = (read_number(&path, "energy_now"), read_number(&path, "energy_full")) {
+                    now += energy_now;
+                    full += energy_full;
+                    rate += read_number(&path, "power_now").unwrap_or_default().abs();
+                } else if let (Some(charge_now), Some(charge_full)) = (read_number(&path, "charge_now"), read_number(&path, "charge_full"))
+                {
+                    now += charge_now;
+                    full += charge_full;
+                    rate += read_number(&path, "current_now").unwrap_or_default().abs();
+                }
+            }
+            _ => (),
+        }
+    }
+
+    let status = status?;
+    let percentage = if full > 0.0 {
+        (now / full * 100.0).round().min(100.0) as u8
+    } else {
+        capacity.unwrap_or_default()
+    };
+    let time_remaining = match status {
+        _ if rate <= 0.0 => None,
+        BatteryStatus::Discharging => Some((now / rate * 60.0).round().min(u16::MAX as f64 - 1.0) as u16),
+        BatteryStatus::Charging => Some(((full - now).max(0.0) / rate * 60.0).round().min(u16::MAX as f64 - 1.0) as u16),
+        _ => None,
+    };
+
+    return Some(BatteryState {
+        percentage,
+        status,
+        ac_online,
+        time_remaining,
+    });
+}
+
+/// Kernel uevents of the `power_supply` subsystem, sent when a charger is plugged in or unplugged
+pub struct PowerEvents {
+    fd: OwnedFd,
+}
+
+impl PowerEvents {
+    pub fn new() -> Option<Self> {
+        unsafe {
+            let fd = libc::socket(
+                libc::AF_NETLINK,
+                libc::SOCK_DGRAM | libc::SOCK_CLOEXEC,
+                libc::NETLINK_KOBJECT_UEVENT,
+            );
+            if fd < 0 {
+                tracing::error!("Battery Provider can not open uevent socket: {}", io::Error::last_os_error());
+                return None;
+            }
+
+            let fd = OwnedFd::from_raw_fd(fd);
+            let mut address: libc::sockaddr_nl = std::mem::zeroed();
+            address.nl_family = libc::AF_NETLINK as u16;
+            address.nl_groups = 1; // kernel events, udev events are not needed
+            let result = libc::bind(
+                fd.as_raw_fd(),
+                &address as *const libc::sockaddr_nl as *const libc::sockaddr,
+                std::mem::size_of::<libc::sockaddr_nl>() as u32,
+            );
+            if result < 0 {
+                tracing::error!("Battery Provider can not listen to uevents: {}", io::Error::last_os_error());
+                return None;
+            }
+
+            return Some(Self { fd });
+        }
+    }
+
+    /// Returns `true` if any power supply was changed, returns earlier on unrelated uevents
+    pub fn wait(&self, timeout: Duration) -> bool {
+        let mut poll_fd = libc::pollfd {
+            fd: self.fd.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let mut timeout = timeout.as_millis() as i32;
+        let mut is_changed = false;
+        let mut buffer = [0u8; 8192];
+        while unsafe { libc::poll(&mut poll_fd, 1, timeout) } > 0 {
+            let size = unsafe { libc::recv(self.fd.as_raw_fd(), buffer.as_mut_ptr() as *mut libc::c_void, buffer.len(), 0) };
+            if size <= 0 {
+                break;
+            }
+
+            // uevent is `ACTION@DEVPATH` followed by `KEY=VALUE` pairs, all separated by zero bytes
+            is_changed |= buffer[..size as usize].split(|x| *x == 0).any(|x| x == b"SUBSYSTEM=power_supply");
+            // events come in bursts, the rest of them is read without waiting
+            timeout = 0;
+        }
+
+        return is_changed;
+    }
+}
@@ -0,0 +1,57 @@
+use std::process::Command;
+use std::time::Duration;
+
+use super::{BatteryState, BatteryStatus};
+
+// pmset output example:
+// Now drawing from 'Battery Power'
+//  -InternalBattery-0 (id=4653155)	85%; discharging; 4:12 remaining present: true
+fn parse_pmset(output: &str) -> Option<BatteryState> {
+    let ac_online = output.contains("'AC Power'");
+    let battery_line = output.lines().find(|line| line.contains("InternalBattery"))?;
+    let details = battery_line.split('\t').nth(1)?;
+    let mut parts = details.split(';').map(str::trim);
+
+    let percentage = parts.next()?.trim_end_matches('%').parse::<u8>().ok()?.min(100);
+    let status = match parts.next()? {
+        "charging" => BatteryStatus::Charging,
+        "charged" => BatteryStatus::Full,
+        "AC attached" | "finishing charge" => BatteryStatus::NotCharging,
+        _ => BatteryStatus::Discharging,
+    };
+    let time_remaining = parts.next().and_then(|x| x.split(' ').next()).and_then(|x| {
+        let (hours, minutes) = x.split_once(':')?;
+        Some(hours.parse::<u16>().ok()? * 60 + minutes.parse::<u16>().ok()?)
+    });
+
+    return Some(BatteryState {
+        percentage,
+        status,
+        ac_online,
+        time_remaining,
+    });
+}
+
+pub fn get_battery_state() -> Option<BatteryState> {
+    let output = Command::new("pmset").args(["-g", "batt"]).output().ok()?;
+    if !output.status.success() {
+        tracing::error!("Battery Provider failed to get battery state");
+        return None;
+    }
+
+    return parse_pmset(&String::from_utf8_lossy(&output.stdout));
+}
+
+/// pmset has no notifications, so power source is checked on every tick instead
+pub struct PowerEvents;
+
+impl PowerEvents {
+    pub fn new() -> Option<Self> {
+        return Some(Self);
+    }
+
+    pub fn wait(&self, timeout: Duration) -> bool {
+        std::thread::sleep(timeout);
+        return true;
+    }
+}
@@ -0,0 +1,114 @@
+use std::ffi::c_void;
+use std::sync::mpsc;
+use std::time::Duration;
+use windows::Win32::Foundation::{ERROR_SUCCESS, HANDLE};
+use windows::Win32::System::Power::{
+    GetSystemPowerStatus, PowerSettingRegisterNotification, PowerSettingUnregisterNotification, DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS,
+    HPOWERNOTIFY, SYSTEM_POWER_STATUS,
+};
+use windows::Win32::System::SystemServices::GUID_ACDC_POWER_SOURCE;
+use windows::Win32::UI::WindowsAndMessaging::DEVICE_NOTIFY_CALLBACK;
+
+use super::{BatteryState, BatteryStatus};
+
+const BATTERY_FLAG_CHARGING: u8 = 8;
+const BATTERY_FLAG_NO_BATTERY: u8 = 128;
+const BATTERY_FLAG_UNKNOWN: u8 = 255;
+
+pub fn get_battery_state() -> Option<BatteryState> {
+    let mut power_status = SYSTEM_POWER_STATUS::default();
+    unsafe { GetSystemPowerStatus(&mut power_status) }
+        .map_err(|e| tracing::error!("Can not get power status: {}", e))
+        .ok()?;
+
+    if power_status.BatteryFlag & BATTERY_FLAG_NO_BATTERY != 0 || power_status.BatteryFlag == BATTERY_FLAG_UNKNOWN {
+        return None;
+    }
+
+    let ac_online = power_status.ACLineStatus == 1;
+    let percentage = power_status.BatteryLifePercent.min(100);
+    let status = if power_status.BatteryFlag & BATTERY_FLAG_CHARGING != 0 {
+        BatteryStatus::Charging
+    } else if ac_online && percentage == 100 {
+        BatteryStatus::Full
+    } else if ac_online {
+        BatteryStatus::NotCharging
+    } else {
+        BatteryStatus::Discharging
+    };
+
+    // BatteryLifeTime is u32::MAX when unknown or when running on AC power
+    let time_remaining = match power_status.BatteryLifeTime {
+        u32::MAX => None,
+        seconds => Some((seconds / 60).min(u16::MAX as u32 - 1) as u16),
+    };
+
+    return Some(BatteryState {
+        percentage,
+        status,
+        ac_online,
+        time_remaining,
+    });
+}
+
+unsafe extern "system" fn on_power_source_change(context: *const c_void, _type: u32, _setting: *const c_void) -> u32 {
+    let sender = &*(context as *const mpsc::Sender<()>);
+    let _ = sender.send(());
+    return ERROR_SUCCESS.0;
+}
+
+/// Power setting notification of AC/DC power source, the same as `WM_POWERBROADCAST` but without a window
+pub struct PowerEvents {
+    handle: *mut c_void,
+    receiver: mpsc::Receiver<()>,
+    // callback gets pointers to these, so they are kept until the notification is unregistered
+    _sender: Box<mpsc::Sender<()>>,
+    _parameters: Box<DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS>,
+}
+
+impl PowerEvents {
+    pub fn new() -> Option<Self> {
+        let (sender, receiver) = mpsc::channel();
+        let sender = Box::new(sender);
+        let parameters = Box::new(DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS {
+            Callback: Some(on_power_source_change),
+            Context: &*sender as *const mpsc::Sender<()> as *mut c_void,
+        });
+        let mut handle: *mut c_void = std::ptr::null_mut();
+        let result = unsafe {
+            PowerSettingRegisterNotification(
+                &GUID_ACDC_POWER_SOURCE,
+                DEVICE_NOTIFY_CALLBACK,
+                HANDLE(&*parameters as *const DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS as isize),
+                &mut handle,
+            )
+        };
+        if result != ERROR_SUCCESS {
+            tracing::error!("Battery Provider can not register power notification: {:?}", result);
+            return None;
+        }
+
+        return Some(Self {
+            handle,
+            receiver,
+            _sender: sender,
+            _parameters: parameters,
+        });
+    }
+
+    /// Returns `true` if power source was changed
+    pub fn wait(&self, timeout: Duration) -> bool {
+        if self.receiver.recv_timeout(timeout).is_err() {
+            return false;
+        }
+
+        while self.receiver.try_recv().is_ok() {}
+        return true;
+    }
+}
+
+impl Drop for PowerEvents {
+    fn drop(&mut self) {
+        let _ = unsafe { PowerSettingUnregisterNotification(HPOWERNOTIFY(self.handle as isize)) };
+    }
+}
@@ -0,0 +1,214 @@
+mod ics;
+
+use chrono::{DateTime, Utc};
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+use crate::config::CalendarConfig;
+use crate::data_type::DataType;
+
+use super::_base::Provider;
+
+struct UpcomingEvent {
+    uid: String,
+    title: String,
+    start: DateTime<Utc>,
+}
+
+/// Directories (e.g. synced by vdirsyncer) are read as a set of `.ics` files
+fn read_events(path: &Path) -> Vec<ics::Event> {
+    if path.is_dir() {
+        let entries = std::fs::read_dir(path).into_iter().flatten().flatten();
+        let files = entries
+            .map(|x| x.path())
+            .filter(|x| x.extension().is_some_and(|x| x.eq_ignore_ascii_case("ics")));
+        return files.flat_map(|x| read_events(&x)).collect();
+    }
+
+    return match std::fs::read_to_string(path) {
+        Ok(content) => ics::parse(&content),
+        Err(e) => {
+            tracing::error!("Calendar Provider can not read '{}': {}", path.display(), e);
+            vec![]
+        }
+    };
+}
+
+/// Returns next occurrences of all events sorted by start time. All-day events are skipped, as they are not meetings to be reminded of
+fn get_upcoming_events(files: &[String], after: DateTime<Utc>) -> Vec<UpcomingEvent> {
+    let events = files.iter().flat_map(|x| read_events(Path::new(x)));
+    let mut upcoming_events = events
+        .filter(|x| !x.is_all_day)
+        .filter_map(|x| {
+            x.get_next_start(after).map(|start| UpcomingEvent {
+                uid: x.uid,
+                title: x.summary,
+                start,
+            })
+        })
+        .collect::<Vec<_>>();
+    upcoming_events.sort_by_key(|x| x.start);
+    return upcoming_events;
+}
+
+/// Minutes are rounded up, so event starting in 30 seconds is 1 minute away and 0 means it is starting now
+fn get_minutes_until(start: DateTime<Utc>, now: DateTime<Utc>) -> i64 {
+    return ((start - now).num_seconds() + 59) / 60;
+}
+
+fn get_title_data(data_type: DataType, value: &[u8], title: &str) -> Vec<u8> {
+    let mut data = title.as_bytes().to_vec();
+    data.truncate(27);
+    data.insert(0, data.len() as u8);
+    data.splice(0..0, value.iter().copied());
+    data.insert(0, data_type as u8);
+    return data;
+}
+
+/// Returns the next event frame, `u16::MAX` minutes means there are no upcoming events
+fn get_event_data(event: &Option<(i64, String)>) -> Vec<u8> {
+    return match event {
+        Some((minutes, title)) => {
+            let minutes = (*minutes).clamp(0, u16::MAX as i64 - 1) as u16;
+            get_title_data(DataType::Calendar, &minutes.to_le_bytes(), title)
+        }
+        None => get_title_data(DataType::Calendar, &u16::MAX.to_le_bytes(), ""),
+    };
+}
+
+fn send_data(event: &Option<(i64, String)>, data_sender: &broadcast::Sender<Vec<u8>>) {
+    if let Some((minutes, title)) = event {
+        tracing::info!("next calendar event: '{0}' in {1} minutes", title, minutes);
+    }
+
+    let data = get_event_data(event);
+    if let Err(e) = data_sender.send(data) {
+        tracing::error!("Calendar Provider failed to send data: {:?}", e);
+    }
+}
+
+fn send_alert(minutes: u8, title: &str, data_sender: &broadcast::Sender<Vec<u8>>) {
+    tracing::info!("calendar alert: '{0}' in {1} minutes", title, minutes);
+    if let Err(e) = data_sender.send(get_title_data(DataType::CalendarAlert, &[minutes], title)) {
+        tracing::error!("Calendar Provider failed to send data: {:?}", e);
+    }
+}
+
+pub struct CalendarProvider {
+    data_sender: broadcast::Sender<Vec<u8>>,
+    is_started: Arc<AtomicBool>,
+    config: CalendarConfig,
+}
+
+impl CalendarProvider {
+    pub fn new(data_sender: broadcast::Sender<Vec<u8>>, config: CalendarConfig) -> Box<dyn Provider> {
+        let provider = CalendarProvider {
+            data_sender,
+            is_started: Arc::new(AtomicBool::new(false)),
+            config,
+        };
+        return Box::new(provider);
+    }
+}
+
+impl Provider for CalendarProvider {
+    fn start(&self) {
+        tracing::info!("Calendar Provider started");
+        self.is_started.store(true, Relaxed);
+        let data_sender = self.data_sender.clone();
+        let is_started = self.is_started.clone();
+        let config = self.config.clone();
+        std::thread::spawn(move || {
+            let alerts = config.alerts.clone().unwrap_or(vec![5]);
+            let mut synced_event: Option<Option<(i64, String)>> = None;
+            // alerts are sent once per event occurrence and lead time
+            let mut sent_alerts: HashSet<(String, DateTime<Utc>, u8)> = HashSet::new();
+            let mut checked_minute: Option<i64> = None;
+
+            loop {
+                if !is_started.load(Relaxed) {
+                    break;
+                }
+
+                // files are read again every minute, so calendar changes are picked up without restarting
+                let now = Utc::now();
+                let minute = now.timestamp() / 60;
+                if checked_minute != Some(minute) {
+                    checked_minute = Some(minute);
+                    // events which started during the current minute are still upcoming
+                    let minute_start = DateTime::from_timestamp(minute * 60, 0).unwrap_or(now);
+                    let upcoming_events = get_upcoming_events(&config.files, minute_start);
+
+                    for event in &upcoming_events {
+                        let minutes = get_minutes_until(event.start, now);
+                        for alert in alerts.iter().filter(|x| **x as i64 == minutes) {
+                            if sent_alerts.insert((event.uid.clone(), event.start, *alert)) {
+                                send_alert(*alert, &event.title, &data_sender);
+                            }
+                        }
+                    }
+                    sent_alerts.retain(|(_, start, _)| *start >= minute_start);
+
+                    let event = upcoming_events
+                        .into_iter()
+                        .next()
+                        .map(|x| (get_minutes_until(x.start, now), x.title));
+                    if synced_event.as_ref() != Some(&event) {
+                        send_data(&event, &data_sender);
+                        synced_event = Some(event);
+                    }
+                }
+
+                std::thread::sleep(std::time::Duration::from_millis(1000));
+            }
+
+            tracing::info!("Calendar Provider stopped");
+        });
+    }
+
+    fn stop(&self) {
+        self.is_started.store(false, Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the frame which is sent at `now` for events from all fixtures
+    fn get_frame(now: &str) -> Vec<u8> {
+        let now = now.parse::<DateTime<Utc>>().unwrap();
+        let fixtures = format!("{}/src/providers/calendar/fixtures", env!("CARGO_MANIFEST_DIR"));
+        let event = get_upcoming_events(&[fixtures], now)
+            .into_iter()
+            .next()
+            .map(|x| (get_minutes_until(x.start, now), x.title));
+        return get_event_data(&event);
+    }
+
+    fn frame(minutes: u16, title: &str) -> Vec<u8> {
+        let mut data = vec![DataType::Calendar as u8];
+        data.extend(minutes.to_le_bytes());
+        data.push(title.len() as u8);
+        data.extend(title.as_bytes());
+        return data;
+    }
+
+    #[test]
+    fn sends_next_event_frames() {
+        // the first standup, title is truncated to 27 bytes
+        assert_eq!(get_frame("2024-01-01T08:00:00Z"), frame(30, "Team standup with a long de"));
+        // all-day holiday is skipped, excluded standup on Wednesday is replaced by the last lunch
+        assert_eq!(get_frame("2024-01-02T13:00:00Z"), frame(23 * 60, "Lunch"));
+        // lunch count is over, standup on Monday is moved to 11:00
+        assert_eq!(get_frame("2024-01-03T13:00:00Z"), frame(285 * 60, "Moved standup"));
+        assert_eq!(get_frame("2024-01-15T09:59:30Z"), frame(1, "Moved standup"));
+        assert_eq!(get_frame("2024-01-15T10:30:00Z"), frame(46 * 60, "Team standup with a long de"));
+        // too far away events are sent with the maximum number of minutes
+        assert_eq!(get_frame("2024-04-01T00:00:00Z"), frame(u16::MAX - 1, "Quarterly review"));
+        assert_eq!(get_frame("2024-07-02T00:00:00Z"), frame(u16::MAX, ""));
+    }
+}
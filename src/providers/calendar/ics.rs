@@ -0,0 +1,537 @@
+use chrono::{DateTime, Datelike, Duration, Local, LocalResult, Months, NaiveDate, NaiveDateTime, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+
+/// Safety limit for recurrence expansion, e.g. 10000 periods of a daily event is more than 27 years
+const MAX_PERIODS: u32 = 10000;
+
+#[derive(Clone, Copy)]
+enum Zone {
+    Utc,
+    Local,
+    Tz(Tz),
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+#[derive(Clone)]
+struct Recurrence {
+    frequency: Frequency,
+    interval: u32,
+    count: Option<u32>,
+    until: Option<DateTime<Utc>>,
+    /// Weekdays with optional ordinal for monthly rules: `2TU` is `(2, Tue)`, `-1FR` is `(-1, Fri)`, `MO` is `(0, Mon)`
+    by_day: Vec<(i32, Weekday)>,
+    by_month_day: Vec<i32>,
+}
+
+#[derive(Clone)]
+pub struct Event {
+    pub uid: String,
+    pub summary: String,
+    pub is_all_day: bool,
+    is_cancelled: bool,
+    start: NaiveDateTime,
+    zone: Zone,
+    recurrence: Option<Recurrence>,
+    recurrence_id: Option<DateTime<Utc>>,
+    exceptions: Vec<DateTime<Utc>>,
+}
+
+struct Property {
+    name: String,
+    params: Vec<(String, String)>,
+    value: String,
+}
+
+impl Property {
+    fn get_param(&self, name: &str) -> Option<&str> {
+        return self.params.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str());
+    }
+}
+
+/// Long lines are folded by inserting a line break followed by a space or a tab
+fn unfold(content: &str) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    for line in content.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    return lines;
+}
+
+/// `DTSTART;TZID="Europe/Berlin":20240105T090000` - params can be quoted and contain `:` and `;`
+fn parse_property(line: &str) -> Option<Property> {
+    let mut is_quoted = false;
+    let mut separators = vec![];
+    let mut value_start = None;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => is_quoted = !is_quoted,
+            ';' if !is_quoted => separators.push(i),
+            ':' if !is_quoted => {
+                value_start = Some(i);
+                break;
+            }
+            _ => (),
+        }
+    }
+
+    let value_start = value_start?;
+    let name_end = separators.first().copied().unwrap_or(value_start);
+    let mut params = vec![];
+    for (i, start) in separators.iter().enumerate() {
+        let end = separators.get(i + 1).copied().unwrap_or(value_start);
+        if let Some((key, value)) = line[start + 1..end].split_once('=') {
+            params.push((key.to_uppercase(), value.trim_matches('"').to_string()));
+        }
+    }
+
+    return Some(Property {
+        name: line[..name_end].to_uppercase(),
+        params,
+        value: line[value_start + 1..].to_string(),
+    });
+}
+
+fn unescape(value: &str) -> String {
+    let mut result = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') | Some('N') => result.push(' '),
+            Some(c) => result.push(c),
+            None => (),
+        }
+    }
+    return result;
+}
+
+/// Unknown zones (e.g. Windows names used by Outlook) are treated as local time
+fn get_zone(property: &Property) -> Zone {
+    if property.value.ends_with('Z') {
+        return Zone::Utc;
+    }
+
+    return match property.get_param("TZID").map(|x| x.parse::<Tz>()) {
+        Some(Ok(tz)) => Zone::Tz(tz),
+        Some(Err(_)) => {
+            tracing::debug!(
+                "unknown calendar time zone '{}', local time is used",
+                property.get_param("TZID").unwrap_or_default()
+            );
+            Zone::Local
+        }
+        None => Zone::Local,
+    };
+}
+
+/// Returns date-time and `true` if it is a date without time
+fn parse_date_time(value: &str) -> Option<(NaiveDateTime, bool)> {
+    let value = value.trim().trim_end_matches('Z');
+    if let Ok(date_time) = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S") {
+        return Some((date_time, false));
+    }
+
+    let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+    return Some((date.and_hms_opt(0, 0, 0)?, true));
+}
+
+/// Time which does not exist because of DST transition is moved forward by an hour
+fn to_utc(date_time: NaiveDateTime, zone: Zone) -> Option<DateTime<Utc>> {
+    fn resolve<T: TimeZone>(tz: &T, date_time: NaiveDateTime) -> Option<DateTime<Utc>> {
+        return match tz.from_local_datetime(&date_time) {
+            LocalResult::Single(x) | LocalResult::Ambiguous(x, _) => Some(x.with_timezone(&Utc)),
+            LocalResult::None => tz
+                .from_local_datetime(&(date_time + Duration::hours(1)))
+                .earliest()
+                .map(|x| x.with_timezone(&Utc)),
+        };
+    }
+
+    return match zone {
+        Zone::Utc => Some(Utc.from_utc_datetime(&date_time)),
+        Zone::Local => resolve(&Local, date_time),
+        Zone::Tz(tz) => resolve(&tz, date_time),
+    };
+}
+
+fn parse_weekday(value: &str) -> Option<Weekday> {
+    return match value {
+        "MO" => Some(Weekday::Mon),
+        "TU" => Some(Weekday::Tue),
+        "WE" => Some(Weekday::Wed),
+        "TH" => Some(Weekday::Thu),
+        "FR" => Some(Weekday::Fri),
+        "SA" => Some(Weekday::Sat),
+        "SU" => Some(Weekday::Sun),
+        _ => None,
+    };
+}
+
+/// `FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE;UNTIL=20241231T235959Z`
+fn parse_recurrence(value: &str, zone: Zone) -> Option<Recurrence> {
+    let mut recurrence = Recurrence {
+        frequency: Frequency::Daily,
+        interval: 1,
+        count: None,
+        until: None,
+        by_day: vec![],
+        by_month_day: vec![],
+    };
+    let mut frequency = None;
+
+    for part in value.split(';') {
+        let (key, value) = part.split_once('=')?;
+        match key.to_uppercase().as_str() {
+            "FREQ" => {
+                frequency = match value.to_uppercase().as_str() {
+                    "DAILY" => Some(Frequency::Daily),
+                    "WEEKLY" => Some(Frequency::Weekly),
+                    "MONTHLY" => Some(Frequency::Monthly),
+                    "YEARLY" => Some(Frequency::Yearly),
+                    _ => None,
+                }
+            }
+            "INTERVAL" => recurrence.interval = value.parse::<u32>().ok()?.max(1),
+            "COUNT" => recurrence.count = value.parse::<u32>().ok(),
+            "UNTIL" => {
+                let (until, is_date) = parse_date_time(value)?;
+                // date-only UNTIL includes the whole day
+                let until = if is_date {
+                    until + Duration::days(1) - Duration::seconds(1)
+                } else {
+                    until
+                };
+                let zone = if value.ends_with('Z') { Zone::Utc } else { zone };
+                recurrence.until = to_utc(until, zone);
+            }
+            "BYDAY" => {
+                for day in value.split(',') {
+                    let (ordinal, weekday) = day.split_at(day.len().saturating_sub(2));
+                    recurrence
+                        .by_day
+                        .push((ordinal.parse::<i32>().unwrap_or(0), parse_weekday(weekday)?));
+                }
+            }
+            "BYMONTHDAY" => recurrence.by_month_day = value.split(',').filter_map(|x| x.parse::<i32>().ok()).collect(),
+            _ => (),
+        }
+    }
+
+    recurrence.frequency = frequency?;
+    return Some(recurrence);
+}
+
+/// Returns the day of month, negative values are counted from the end of month
+fn get_month_day(year: i32, month: u32, day: i32) -> Option<NaiveDate> {
+    if day > 0 {
+        return NaiveDate::from_ymd_opt(year, month, day as u32);
+    }
+
+    let next_month = NaiveDate::from_ymd_opt(year, month, 1)?.checked_add_months(Months::new(1))?;
+    let date = next_month.checked_sub_signed(Duration::days(-day as i64))?;
+    return (date.month() == month).then_some(date);
+}
+
+/// Returns all weekdays of the month for ordinal 0, otherwise the nth one (negative are counted from the end)
+fn get_month_weekdays(year: i32, month: u32, ordinal: i32, weekday: Weekday) -> Vec<NaiveDate> {
+    let dates = (1..=31)
+        .filter_map(|day| NaiveDate::from_ymd_opt(year, month, day))
+        .filter(|x| x.weekday() == weekday)
+        .collect::<Vec<_>>();
+
+    return match ordinal {
+        0 => dates,
+        1.. => dates.get(ordinal as usize - 1).into_iter().copied().collect(),
+        _ => dates
+            .len()
+            .checked_sub(-ordinal as usize)
+            .and_then(|x| dates.get(x))
+            .into_iter()
+            .copied()
+            .collect(),
+    };
+}
+
+impl Recurrence {
+    /// Returns sorted dates of the nth period of the rule starting from `start`
+    fn get_period_dates(&self, start: NaiveDate, period: u32) -> Vec<NaiveDate> {
+        let step = period * self.interval;
+        let mut dates = match self.frequency {
+            Frequency::Daily => start.checked_add_signed(Duration::days(step as i64)).into_iter().collect(),
+            Frequency::Weekly => {
+                let week_start = start - Duration::days(start.weekday().num_days_from_monday() as i64) + Duration::weeks(step as i64);
+                if self.by_day.is_empty() {
+                    vec![week_start + Duration::days(start.weekday().num_days_from_monday() as i64)]
+                } else {
+                    self.by_day
+                        .iter()
+                        .map(|(_, day)| week_start + Duration::days(day.num_days_from_monday() as i64))
+                        .collect()
+                }
+            }
+            Frequency::Monthly => {
+                let Some(month) = start.with_day(1).and_then(|x| x.checked_add_months(Months::new(step))) else {
+                    return vec![];
+                };
+                if !self.by_day.is_empty() {
+                    self.by_day
+                        .iter()
+                        .flat_map(|(ordinal, day)| get_month_weekdays(month.year(), month.month(), *ordinal, *day))
+                        .collect()
+                } else if !self.by_month_day.is_empty() {
+                    self.by_month_day
+                        .iter()
+                        .filter_map(|day| get_month_day(month.year(), month.month(), *day))
+                        .collect()
+                } else {
+                    // months without this day (e.g. 31st) are skipped
+                    get_month_day(month.year(), month.month(), start.day() as i32).into_iter().collect()
+                }
+            }
+            Frequency::Yearly => NaiveDate::from_ymd_opt(start.year() + step as i32, start.month(), start.day())
+                .into_iter()
+                .collect(),
+        };
+
+        dates.sort();
+        dates.dedup();
+        return dates;
+    }
+}
+
+impl Event {
+    fn get_start(&self, start: NaiveDateTime) -> Option<DateTime<Utc>> {
+        return to_utc(start, self.zone);
+    }
+
+    /// Returns the first occurrence which starts at or after `after`
+    pub fn get_next_start(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let Some(recurrence) = &self.recurrence else {
+            return self.get_start(self.start).filter(|x| *x >= after);
+        };
+
+        let mut count = 0;
+        for period in 0..MAX_PERIODS {
+            for date in recurrence.get_period_dates(self.start.date(), period) {
+                let start = date.and_time(self.start.time());
+                if start < self.start {
+                    continue;
+                }
+
+                let Some(start) = self.get_start(start) else {
+                    continue;
+                };
+                if recurrence.until.is_some_and(|x| start > x) || recurrence.count.is_some_and(|x| count >= x) {
+                    return None;
+                }
+
+                // excluded occurrences are still counted
+                count += 1;
+                if start >= after && !self.exceptions.contains(&start) {
+                    return Some(start);
+                }
+            }
+        }
+
+        return None;
+    }
+}
+
+fn parse_event(properties: &[Property]) -> Option<Event> {
+    let start_property = properties.iter().find(|x| x.name == "DTSTART")?;
+    let (start, is_date) = parse_date_time(&start_property.value)?;
+    let zone = get_zone(start_property);
+    let get_value = |name: &str| properties.iter().find(|x| x.name == name).map(|x| x.value.as_str());
+
+    let mut exceptions = vec![];
+    for property in properties.iter().filter(|x| x.name == "EXDATE") {
+        let zone = get_zone(property);
+        exceptions.extend(
+            property
+                .value
+                .split(',')
+                .filter_map(parse_date_time)
+                .filter_map(|(x, _)| to_utc(x, zone)),
+        );
+    }
+
+    let recurrence_id = properties
+        .iter()
+        .find(|x| x.name == "RECURRENCE-ID")
+        .and_then(|x| parse_date_time(&x.value).and_then(|(value, _)| to_utc(value, get_zone(x))));
+
+    return Some(Event {
+        uid: get_value("UID").unwrap_or_default().to_string(),
+        summary: unescape(get_value("SUMMARY").unwrap_or_default()),
+        is_all_day: is_date || start_property.get_param("VALUE") == Some("DATE"),
+        is_cancelled: get_value("STATUS").is_some_and(|x| x.eq_ignore_ascii_case("CANCELLED")),
+        start,
+        zone,
+        recurrence: get_value("RRULE").and_then(|x| parse_recurrence(x, zone)),
+        recurrence_id,
+        exceptions,
+    });
+}
+
+/// Parses all events of the calendar. Modified occurrences (with `RECURRENCE-ID`) are returned as separate events
+/// and excluded from their recurring event, cancelled events are skipped.
+pub fn parse(content: &str) -> Vec<Event> {
+    let mut events = vec![];
+    let mut properties: Option<Vec<Property>> = None;
+    let mut depth = 0;
+
+    for line in unfold(content) {
+        let Some(property) = parse_property(&line) else {
+            continue;
+        };
+
+        match (property.name.as_str(), property.value.to_uppercase().as_str()) {
+            ("BEGIN", "VEVENT") => properties = Some(vec![]),
+            ("END", "VEVENT") => {
+                if let Some(event) = properties.take().and_then(|x| parse_event(&x)) {
+                    events.push(event);
+                }
+            }
+            // nested components like VALARM have their own properties
+            ("BEGIN", _) if properties.is_some() => depth += 1,
+            ("END", _) if properties.is_some() => depth -= 1,
+            _ if depth == 0 => {
+                if let Some(properties) = properties.as_mut() {
+                    properties.push(property);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    let overrides = events
+        .iter()
+        .filter_map(|x| x.recurrence_id.map(|recurrence_id| (x.uid.clone(), recurrence_id)))
+        .collect::<Vec<_>>();
+    for event in events.iter_mut().filter(|x| x.recurrence.is_some()) {
+        event
+            .exceptions
+            .extend(overrides.iter().filter(|(uid, _)| *uid == event.uid).map(|(_, x)| *x));
+    }
+
+    events.retain(|x| !x.is_cancelled);
+    return events;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = include_str!("fixtures/work.ics");
+
+    fn get_event<'a>(events: &'a [Event], summary: &str) -> &'a Event {
+        return events.iter().find(|x| x.summary == summary).unwrap();
+    }
+
+    fn at(value: &str) -> DateTime<Utc> {
+        return value.parse::<DateTime<Utc>>().unwrap();
+    }
+
+    /// Collects occurrences one by one, as the provider does when time passes
+    fn get_starts(event: &Event, after: DateTime<Utc>) -> Vec<DateTime<Utc>> {
+        let mut starts = vec![];
+        let mut after = after;
+        while let Some(start) = event.get_next_start(after) {
+            starts.push(start);
+            after = start + Duration::seconds(1);
+        }
+        return starts;
+    }
+
+    #[test]
+    fn parses_folded_lines_and_all_day_events() {
+        let events = parse(FIXTURE);
+        assert_eq!(events.len(), 6);
+
+        let standup = get_event(&events, "Team standup with a long description which is folded by the calendar app");
+        assert_eq!(standup.uid, "standup");
+        assert!(!standup.is_all_day);
+
+        let holiday = get_event(&events, "Holiday");
+        assert!(holiday.is_all_day);
+        assert_eq!(
+            holiday.get_next_start(at("2024-01-01T00:00:00Z")),
+            Some(to_utc(holiday.start, Zone::Local).unwrap())
+        );
+    }
+
+    #[test]
+    fn resolves_time_zones_with_dst() {
+        let events = parse(FIXTURE);
+        // New York is UTC-4 in July and Berlin is UTC+1 in January
+        let review = get_event(&events, "Quarterly review");
+        assert_eq!(review.get_next_start(at("2024-01-01T00:00:00Z")), Some(at("2024-07-01T13:00:00Z")));
+        assert_eq!(review.get_next_start(at("2024-07-01T13:00:01Z")), None);
+
+        let moved = get_event(&events, "Moved standup");
+        assert_eq!(moved.get_next_start(at("2024-01-01T00:00:00Z")), Some(at("2024-01-15T10:00:00Z")));
+    }
+
+    #[test]
+    fn expands_recurrence_with_exceptions_and_overrides() {
+        let events = parse(FIXTURE);
+        // every other week on Monday and Wednesday until the end of January, without excluded and moved occurrences
+        let standup = events.iter().find(|x| x.uid == "standup" && x.recurrence.is_some()).unwrap();
+        assert_eq!(
+            get_starts(standup, at("2024-01-01T00:00:00Z")),
+            vec![
+                at("2024-01-01T08:30:00Z"),
+                at("2024-01-17T08:30:00Z"),
+                at("2024-01-29T08:30:00Z"),
+                at("2024-01-31T08:30:00Z"),
+            ]
+        );
+
+        let lunch = get_event(&events, "Lunch");
+        assert_eq!(
+            get_starts(lunch, at("2024-01-01T00:00:00Z")),
+            vec![at("2024-01-01T12:00:00Z"), at("2024-01-02T12:00:00Z"), at("2024-01-03T12:00:00Z")]
+        );
+
+        // last Friday of the month, New York switches to DST in March
+        let retro = get_event(&events, "Retro");
+        assert_eq!(
+            get_starts(retro, at("2024-01-01T00:00:00Z")),
+            vec![at("2024-01-26T21:00:00Z"), at("2024-02-23T21:00:00Z"), at("2024-03-29T20:00:00Z")]
+        );
+        assert_eq!(get_starts(retro, at("2024-02-24T00:00:00Z")), vec![at("2024-03-29T20:00:00Z")]);
+    }
+
+    #[test]
+    fn parses_recurrence_rules() {
+        let recurrence = parse_recurrence("FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE;UNTIL=20240131T235959Z", Zone::Utc).unwrap();
+        assert!(recurrence.frequency == Frequency::Weekly);
+        assert_eq!(recurrence.interval, 2);
+        assert_eq!(recurrence.by_day, vec![(0, Weekday::Mon), (0, Weekday::Wed)]);
+        assert_eq!(recurrence.until, Some(at("2024-01-31T23:59:59Z")));
+
+        let recurrence = parse_recurrence("FREQ=MONTHLY;BYDAY=2TU,-1FR;COUNT=5", Zone::Utc).unwrap();
+        assert_eq!(recurrence.by_day, vec![(2, Weekday::Tue), (-1, Weekday::Fri)]);
+        assert_eq!(recurrence.count, Some(5));
+
+        // date-only UNTIL includes the whole day
+        let recurrence = parse_recurrence("FREQ=DAILY;UNTIL=20240131", Zone::Utc).unwrap();
+        assert_eq!(recurrence.until, Some(at("2024-01-31T23:59:59Z")));
+
+        assert!(parse_recurrence("FREQ=SECONDLY", Zone::Utc).is_none());
+    }
+}
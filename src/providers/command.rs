@@ -0,0 +1,285 @@
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+
+use crate::config::{CommandConfig, CommandMode};
+use crate::utils::value_parser;
+
+use super::_base::Provider;
+
+/// Long-running command is started again after this delay when it exits
+const RESTART_DELAY_SECONDS: u64 = 5;
+const DEFAULT_TIMEOUT_SECONDS: u64 = 30;
+
+fn get_command(command: &str) -> Command {
+    #[cfg(target_os = "windows")]
+    let command = {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        // cmd does its own parsing of the command line, quoting it as an argument would break commands with quotes
+        let mut process = Command::new("cmd");
+        process.arg("/C").raw_arg(command).creation_flags(CREATE_NO_WINDOW);
+        process
+    };
+
+    // own process group, so processes started by the shell are killed together with it
+    #[cfg(not(target_os = "windows"))]
+    let command = {
+        use std::os::unix::process::CommandExt;
+        let mut process = Command::new("sh");
+        process.args(["-c", command]).process_group(0);
+        process
+    };
+
+    return command;
+}
+
+/// Kills the shell with all processes it started, otherwise e.g. `sleep` in a pipeline would keep running
+fn kill(child: &mut Child) {
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        let _ = Command::new("taskkill")
+            .args(["/F", "/T", "/PID", &child.id().to_string()])
+            .creation_flags(CREATE_NO_WINDOW)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    unsafe {
+        libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
+    }
+
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+fn send_data(data_type: u8, value: &[u8], data_sender: &broadcast::Sender<Vec<u8>>) {
+    let mut data = vec![data_type];
+    data.extend_from_slice(value);
+    if let Err(e) = data_sender.send(data) {
+        tracing::error!("Command Provider failed to send data: {:?}", e);
+    }
+}
+
+fn handle_output(output: &str, config: &CommandConfig, synced_data: &mut Option<Vec<u8>>, data_sender: &broadcast::Sender<Vec<u8>>) {
+    let Some(data) = value_parser::parse(output, config.parser.unwrap_or_default(), config.json_path.as_deref()) else {
+        tracing::error!("Command Provider can not parse output of '{}': {}", config.command, output.trim());
+        return;
+    };
+
+    if synced_data.as_ref() != Some(&data) {
+        tracing::info!("'{}' returned {:?}", config.command, data);
+        send_data(config.data_type, &data, data_sender);
+        *synced_data = Some(data);
+    }
+}
+
+/// Command is killed when it does not exit in time or the provider is stopped
+fn run_once(
+    config: &CommandConfig,
+    synced_data: &mut Option<Vec<u8>>,
+    data_sender: &broadcast::Sender<Vec<u8>>,
+    is_started: &Arc<AtomicBool>,
+) {
+    let mut child = match get_command(&config.command).stdin(Stdio::null()).stdout(Stdio::piped()).spawn() {
+        Ok(child) => child,
+        Err(e) => return tracing::error!("Command Provider can not run '{}': {}", config.command, e),
+    };
+
+    // output is read while waiting, so the command is not blocked on a full pipe
+    let (output_sender, output_receiver) = mpsc::channel::<Vec<u8>>();
+    if let Some(mut stdout) = child.stdout.take() {
+        std::thread::spawn(move || {
+            let mut output = vec![];
+            let _ = stdout.read_to_end(&mut output);
+            let _ = output_sender.send(output);
+        });
+    }
+
+    let deadline = Instant::now() + Duration::from_secs(config.timeout.unwrap_or(DEFAULT_TIMEOUT_SECONDS).max(1));
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if !is_started.load(Relaxed) => return kill(&mut child),
+            Ok(None) if Instant::now() >= deadline => {
+                tracing::error!("Command Provider: '{}' timed out", config.command);
+                return kill(&mut child);
+            }
+            Ok(None) => std::thread::sleep(Duration::from_millis(100)),
+            Err(e) => {
+                tracing::error!("Command Provider can not wait for '{}': {}", config.command, e);
+                return kill(&mut child);
+            }
+        }
+    };
+
+    if !status.success() {
+        return tracing::error!("Command Provider: '{}' exited with {}", config.command, status);
+    }
+
+    // background processes started by the command can keep the pipe open, they are not waited for
+    match output_receiver.recv_timeout(Duration::from_millis(500)) {
+        Ok(output) => handle_output(&String::from_utf8_lossy(&output), config, synced_data, data_sender),
+        Err(_) => tracing::error!("Command Provider: output of '{}' is not closed", config.command),
+    }
+}
+
+/// Every line of the output is a new value, returns when the command exits or the provider is stopped
+fn read_lines(
+    child: &mut Child,
+    config: &CommandConfig,
+    synced_data: &mut Option<Vec<u8>>,
+    data_sender: &broadcast::Sender<Vec<u8>>,
+    is_started: &Arc<AtomicBool>,
+) {
+    let Some(stdout) = child.stdout.take() else {
+        return;
+    };
+
+    // reading is blocking, so lines are passed from another thread to keep checking if provider is stopped
+    let (line_sender, line_receiver) = mpsc::channel::<String>();
+    std::thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            if line_sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    loop {
+        if !is_started.load(Relaxed) {
+            break;
+        }
+
+        match line_receiver.recv_timeout(Duration::from_millis(100)) {
+            Ok(line) => handle_output(&line, config, synced_data, data_sender),
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                tracing::warn!("Command Provider: '{}' exited", config.command);
+                break;
+            }
+        }
+    }
+}
+
+fn sleep_while_started(seconds: u64, is_started: &Arc<AtomicBool>) {
+    for _ in 0..seconds {
+        if !is_started.load(Relaxed) {
+            break;
+        }
+        std::thread::sleep(Duration::from_secs(1));
+    }
+}
+
+pub struct CommandProvider {
+    data_sender: broadcast::Sender<Vec<u8>>,
+    is_started: Arc<AtomicBool>,
+    config: CommandConfig,
+}
+
+impl CommandProvider {
+    pub fn new(data_sender: broadcast::Sender<Vec<u8>>, config: CommandConfig) -> Box<dyn Provider> {
+        let provider = CommandProvider {
+            data_sender,
+            is_started: Arc::new(AtomicBool::new(false)),
+            config,
+        };
+        return Box::new(provider);
+    }
+}
+
+impl Provider for CommandProvider {
+    fn start(&self) {
+        tracing::info!("Command Provider started for '{}'", self.config.command);
+        self.is_started.store(true, Relaxed);
+        let data_sender = self.data_sender.clone();
+        let is_started = self.is_started.clone();
+        let config = self.config.clone();
+        std::thread::spawn(move || {
+            let mut synced_data: Option<Vec<u8>> = None;
+            loop {
+                if !is_started.load(Relaxed) {
+                    break;
+                }
+
+                match config.mode.unwrap_or_default() {
+                    CommandMode::Interval => {
+                        run_once(&config, &mut synced_data, &data_sender, &is_started);
+                        sleep_while_started(config.interval.unwrap_or(60).max(1), &is_started);
+                    }
+                    CommandMode::Lines => {
+                        match get_command(&config.command).stdin(Stdio::null()).stdout(Stdio::piped()).spawn() {
+                            Ok(mut child) => {
+                                read_lines(&mut child, &config, &mut synced_data, &data_sender, &is_started);
+                                kill(&mut child);
+                            }
+                            Err(e) => tracing::error!("Command Provider can not run '{}': {}", config.command, e),
+                        }
+                        sleep_while_started(RESTART_DELAY_SECONDS, &is_started);
+                    }
+                }
+            }
+
+            tracing::info!("Command Provider stopped for '{}'", config.command);
+        });
+    }
+
+    fn stop(&self) {
+        self.is_started.store(false, Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ValueParser;
+
+    fn get_config(command: &str, timeout: u64) -> CommandConfig {
+        return CommandConfig {
+            command: command.to_string(),
+            data_type: 0xE0,
+            mode: None,
+            interval: None,
+            timeout: Some(timeout),
+            parser: Some(ValueParser::Integer),
+            json_path: None,
+        };
+    }
+
+    #[test]
+    fn sends_command_output() {
+        let (data_sender, mut data_receiver) = broadcast::channel::<Vec<u8>>(1);
+        run_once(&get_config("echo 42", 5), &mut None, &data_sender, &Arc::new(AtomicBool::new(true)));
+        assert_eq!(data_receiver.try_recv().unwrap(), vec![0xE0, 42, 0, 0, 0]);
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn kills_timed_out_command_with_its_children() {
+        let pid_path = std::env::temp_dir().join(format!("qmk-hid-host-command-{}", std::process::id()));
+        let command = format!("echo $$ > {}; sleep 30 | cat", pid_path.display());
+        let (data_sender, mut data_receiver) = broadcast::channel::<Vec<u8>>(1);
+
+        let started_at = Instant::now();
+        run_once(&get_config(&command, 1), &mut None, &data_sender, &Arc::new(AtomicBool::new(true)));
+        assert!(started_at.elapsed() < Duration::from_secs(5));
+        assert!(data_receiver.try_recv().is_err());
+
+        // shell is the leader of the group, `sleep` and `cat` are gone as well once init reaps them
+        let pid = std::fs::read_to_string(&pid_path).unwrap().trim().parse::<libc::pid_t>().unwrap();
+        let _ = std::fs::remove_file(&pid_path);
+        let is_group_alive = || unsafe { libc::kill(-pid, 0) } == 0;
+        let started_at = Instant::now();
+        while is_group_alive() && started_at.elapsed() < Duration::from_secs(5) {
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        assert!(!is_group_alive());
+    }
+}
@@ -0,0 +1,310 @@
+use std::io::Read;
+use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::broadcast;
+
+use crate::config::{FileConfig, FileFormat};
+
+use super::_base::Provider;
+
+/// Only the beginning of the file fits into a frame, so there is no need to read more
+const MAX_FILE_SIZE: u64 = 4096;
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Missing file is read as empty, so deleting it clears the value on the keyboard
+fn read_file(path: &str) -> Vec<u8> {
+    let mut content = vec![];
+    if let Ok(file) = std::fs::File::open(path) {
+        if let Err(e) = file.take(MAX_FILE_SIZE).read_to_end(&mut content) {
+            tracing::error!("File Provider can not read '{}': {}", path, e);
+        }
+    }
+    return content;
+}
+
+/// Text is sent as 1-byte length and up to 30 bytes without surrounding whitespace, bytes are sent as is up to 31 bytes
+fn get_file_data(content: &[u8], format: FileFormat) -> Vec<u8> {
+    return match format {
+        FileFormat::Text => {
+            let mut data = String::from_utf8_lossy(content).trim().as_bytes().to_vec();
+            data.truncate(30);
+            data.insert(0, data.len() as u8);
+            data
+        }
+        FileFormat::Bytes => content.iter().take(31).copied().collect(),
+    };
+}
+
+/// Watches the directory instead of the file, so atomic renames and the file being deleted
+/// and created again are noticed too
+#[cfg(target_os = "linux")]
+struct Inotify {
+    fd: std::os::fd::OwnedFd,
+    name: std::ffi::OsString,
+}
+
+#[cfg(target_os = "linux")]
+impl Inotify {
+    fn new(path: &std::path::Path) -> Option<Self> {
+        use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+        use std::os::unix::ffi::OsStrExt;
+
+        let name = path.file_name()?.to_os_string();
+        let directory = path
+            .parent()
+            .filter(|x| !x.as_os_str().is_empty())
+            .unwrap_or(std::path::Path::new("."));
+        let directory = std::ffi::CString::new(directory.as_os_str().as_bytes()).ok()?;
+        let fd = unsafe { libc::inotify_init1(libc::IN_CLOEXEC | libc::IN_NONBLOCK) };
+        if fd < 0 {
+            return None;
+        }
+
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+        let mask = libc::IN_CLOSE_WRITE
+            | libc::IN_MODIFY
+            | libc::IN_ATTRIB
+            | libc::IN_CREATE
+            | libc::IN_DELETE
+            | libc::IN_MOVED_FROM
+            | libc::IN_MOVED_TO;
+        if unsafe { libc::inotify_add_watch(fd.as_raw_fd(), directory.as_ptr(), mask) } < 0 {
+            return None;
+        }
+
+        return Some(Inotify { fd, name });
+    }
+
+    /// Returns `Some(true)` if the file was changed, `None` if the directory is not watched anymore, e.g. it was deleted
+    fn wait(&self, timeout: Duration) -> Option<bool> {
+        use std::os::fd::AsRawFd;
+        use std::os::unix::ffi::OsStrExt;
+
+        let mut poll_fd = libc::pollfd {
+            fd: self.fd.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        if unsafe { libc::poll(&mut poll_fd, 1, timeout.as_millis() as i32) } <= 0 {
+            return Some(false);
+        }
+
+        let mut is_changed = false;
+        let mut buffer = [0u8; 4096];
+        loop {
+            let length = unsafe { libc::read(self.fd.as_raw_fd(), buffer.as_mut_ptr() as *mut libc::c_void, buffer.len()) };
+            if length <= 0 {
+                break;
+            }
+
+            // events have variable length, name follows the header and is padded with zeros
+            let mut offset = 0;
+            while offset + std::mem::size_of::<libc::inotify_event>() <= length as usize {
+                let event = unsafe { std::ptr::read_unaligned(buffer[offset..].as_ptr() as *const libc::inotify_event) };
+                let name_start = offset + std::mem::size_of::<libc::inotify_event>();
+                let name = &buffer[name_start..(name_start + event.len as usize).min(length as usize)];
+                if event.mask & libc::IN_IGNORED != 0 {
+                    return None;
+                }
+                is_changed |= name.split(|x| *x == 0).next() == Some(self.name.as_bytes());
+                offset = name_start + event.len as usize;
+            }
+        }
+
+        return Some(is_changed);
+    }
+}
+
+/// Modification time and size, they change on every write, so the file is not read when nothing happened
+fn get_file_state(path: &str) -> Option<(SystemTime, u64)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    return Some((metadata.modified().ok()?, metadata.len()));
+}
+
+/// Uses inotify on Linux, other systems and directories which can not be watched are polled
+struct FileWatcher {
+    path: String,
+    state: Option<(SystemTime, u64)>,
+    #[cfg(target_os = "linux")]
+    inotify: Option<Inotify>,
+}
+
+impl FileWatcher {
+    fn new(path: &str) -> Self {
+        return FileWatcher {
+            path: path.to_string(),
+            state: get_file_state(path),
+            #[cfg(target_os = "linux")]
+            inotify: Inotify::new(std::path::Path::new(path)),
+        };
+    }
+
+    /// Returns `true` if the file could be changed, waits for up to `timeout`
+    fn wait(&mut self, timeout: Duration) -> bool {
+        #[cfg(target_os = "linux")]
+        if let Some(inotify) = &self.inotify {
+            match inotify.wait(timeout) {
+                Some(is_changed) => return is_changed,
+                None => {
+                    tracing::warn!("File Provider can not watch '{}' anymore, it is polled", self.path);
+                    self.inotify = None;
+                    self.state = None;
+                }
+            }
+        }
+
+        std::thread::sleep(timeout.min(POLL_INTERVAL));
+        let state = get_file_state(&self.path);
+        if self.state != state {
+            self.state = state;
+            return true;
+        }
+        return false;
+    }
+}
+
+fn send_data(data_type: u8, value: &[u8], data_sender: &broadcast::Sender<Vec<u8>>) {
+    let mut data = vec![data_type];
+    data.extend_from_slice(value);
+    if let Err(e) = data_sender.send(data) {
+        tracing::error!("File Provider failed to send data: {:?}", e);
+    }
+}
+
+pub struct FileProvider {
+    data_sender: broadcast::Sender<Vec<u8>>,
+    is_started: Arc<AtomicBool>,
+    config: FileConfig,
+}
+
+impl FileProvider {
+    pub fn new(data_sender: broadcast::Sender<Vec<u8>>, config: FileConfig) -> Box<dyn Provider> {
+        let provider = FileProvider {
+            data_sender,
+            is_started: Arc::new(AtomicBool::new(false)),
+            config,
+        };
+        return Box::new(provider);
+    }
+}
+
+impl Provider for FileProvider {
+    fn start(&self) {
+        tracing::info!("File Provider started for '{}'", self.config.path);
+        self.is_started.store(true, Relaxed);
+        let data_sender = self.data_sender.clone();
+        let is_started = self.is_started.clone();
+        let config = self.config.clone();
+        std::thread::spawn(move || {
+            let debounce = Duration::from_millis(config.debounce.unwrap_or(200));
+            let mut watcher = FileWatcher::new(&config.path);
+            let mut is_changed = true;
+            let mut content: Option<Vec<u8>> = None;
+            let mut changed_at = Instant::now();
+            let mut synced_data: Option<Vec<u8>> = None;
+            loop {
+                if !is_started.load(Relaxed) {
+                    break;
+                }
+
+                if is_changed {
+                    let new_content = read_file(&config.path);
+                    if content.as_ref() != Some(&new_content) {
+                        changed_at = Instant::now();
+                        content = Some(new_content);
+                    }
+                }
+
+                // value is sent right away on start, later changes wait until writes settle down
+                if let Some(content) = &content {
+                    if changed_at.elapsed() >= debounce || synced_data.is_none() {
+                        let data = get_file_data(content, config.format.unwrap_or_default());
+                        if synced_data.as_ref() != Some(&data) {
+                            tracing::info!("'{}' changed: {:?}", config.path, data);
+                            send_data(config.data_type, &data, &data_sender);
+                            synced_data = Some(data);
+                        }
+                    }
+                }
+
+                // wakes up when debounce delay is over, otherwise only to check if provider is stopped
+                let timeout = debounce
+                    .checked_sub(changed_at.elapsed())
+                    .filter(|x| !x.is_zero())
+                    .unwrap_or(Duration::from_secs(1));
+                is_changed = watcher.wait(timeout);
+            }
+
+            tracing::info!("File Provider stopped for '{}'", config.path);
+        });
+    }
+
+    fn stop(&self) {
+        self.is_started.store(false, Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Collects changes reported within the timeout, so several events of one write are seen as one change
+    fn is_changed(watcher: &mut FileWatcher) -> bool {
+        let mut is_changed = false;
+        let started_at = Instant::now();
+        while started_at.elapsed() < Duration::from_millis(300) {
+            is_changed |= watcher.wait(Duration::from_millis(100));
+        }
+        return is_changed;
+    }
+
+    #[cfg_attr(not(target_os = "linux"), allow(unused_variables))]
+    fn check_changes(name: &str, is_polled: bool) {
+        let directory = std::env::temp_dir().join(format!("qmk-hid-host-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("status");
+        let mut watcher = FileWatcher::new(path.to_str().unwrap());
+        #[cfg(target_os = "linux")]
+        if is_polled {
+            watcher.inotify = None;
+        }
+        assert!(!is_changed(&mut watcher));
+
+        std::fs::write(&path, "BUILD").unwrap();
+        assert!(is_changed(&mut watcher));
+        assert_eq!(read_file(path.to_str().unwrap()), b"BUILD");
+
+        std::fs::write(directory.join("other"), "ignored").unwrap();
+        assert!(!is_changed(&mut watcher));
+
+        std::fs::write(directory.join("status.tmp"), "DONE").unwrap();
+        std::fs::rename(directory.join("status.tmp"), &path).unwrap();
+        assert!(is_changed(&mut watcher));
+        assert_eq!(read_file(path.to_str().unwrap()), b"DONE");
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(is_changed(&mut watcher));
+        assert_eq!(read_file(path.to_str().unwrap()), b"");
+
+        let _ = std::fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    fn notices_writes_renames_and_deletes() {
+        check_changes("watched", false);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn polls_file_state() {
+        check_changes("polled", true);
+    }
+
+    #[test]
+    fn formats_file_contents() {
+        assert_eq!(get_file_data(b"  BUILD\n", FileFormat::Text), b"\x05BUILD");
+        assert_eq!(get_file_data(&[1, 2, 3], FileFormat::Bytes), vec![1, 2, 3]);
+        assert_eq!(get_file_data(&[7; 40], FileFormat::Bytes).len(), 31);
+    }
+}
@@ -0,0 +1,211 @@
+#![cfg(target_os = "linux")]
+mod hyprland;
+mod sway;
+mod x11;
+
+use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+use crate::config::{AppLayerRule, AppLayersConfig};
+use crate::data_type::DataType;
+
+use super::_base::Provider;
+
+/// Application id is `WM_CLASS` class on X11 and `app_id` (or X11 class for XWayland) on Wayland,
+/// empty if nothing is focused
+#[derive(Default, Clone, PartialEq)]
+pub struct FocusedWindow {
+    pub app_id: String,
+    pub title: String,
+    pub pid: Option<u32>,
+}
+
+fn get_process_name(pid: Option<u32>) -> Option<String> {
+    let comm = std::fs::read_to_string(format!("/proc/{}/comm", pid?)).ok()?;
+    return Some(comm.trim().to_string());
+}
+
+/// Rule matches when all of its conditions match, app and process names are compared case-insensitively
+fn is_rule_match(rule: &AppLayerRule, window: &FocusedWindow, process: Option<&str>) -> bool {
+    let is_app_match = rule.app.as_ref().is_none_or(|x| x.eq_ignore_ascii_case(&window.app_id));
+    let is_process_match = rule
+        .process
+        .as_ref()
+        .is_none_or(|x| process.is_some_and(|p| x.eq_ignore_ascii_case(p)));
+    let is_title_match = rule.title.as_ref().is_none_or(|x| x.is_match(&window.title));
+    return is_app_match && is_process_match && is_title_match;
+}
+
+/// First matching rule wins, default layer is restored when focus moves to an app without a rule
+fn get_layer(window: &FocusedWindow, config: &AppLayersConfig) -> u8 {
+    let process = get_process_name(window.pid);
+    let rule = config.rules.iter().find(|x| is_rule_match(x, window, process.as_deref()));
+    return rule.map(|x| x.layer).unwrap_or(config.default_layer.unwrap_or(0));
+}
+
+fn send_layer(layer: u8, data_sender: &broadcast::Sender<Vec<u8>>) {
+    tracing::info!("new app layer: {}", layer);
+    if let Err(e) = data_sender.send(vec![DataType::SetLayer as u8, layer]) {
+        tracing::error!("Focused App Provider failed to send data: {:?}", e);
+    }
+}
+
+/// Apps are matched case-insensitively, as X11 and Wayland report different case (`Code` and `code`)
+fn get_app_index(app_id: &str, apps: &[String]) -> Option<usize> {
+    return apps.iter().position(|x| x.eq_ignore_ascii_case(app_id));
+}
+
+fn send_data(window: &FocusedWindow, apps: &[String], data_sender: &broadcast::Sender<Vec<u8>>) {
+    tracing::info!("new focused app: '{0}' ('{1}'), app list: {2:?}", window.app_id, window.title, apps);
+    let index = get_app_index(&window.app_id, apps).map(|x| x as u8).unwrap_or(u8::MAX);
+
+    let mut data = window.app_id.clone().into_bytes();
+    data.truncate(29);
+    data.insert(0, data.len() as u8);
+    data.insert(0, index);
+    data.insert(0, DataType::FocusedApp as u8);
+    if let Err(e) = data_sender.send(data) {
+        tracing::error!("Focused App Provider failed to send data: {:?}", e);
+    }
+}
+
+/// Keeps the last sent app and layer, app frame and layer frame of one focus change are sent back to back
+#[derive(Default)]
+struct FocusState {
+    synced_app_id: Option<String>,
+    synced_layer: Option<u8>,
+}
+
+impl FocusState {
+    fn send_changes(
+        &mut self,
+        window: &FocusedWindow,
+        apps: Option<&[String]>,
+        app_layers: Option<&AppLayersConfig>,
+        data_sender: &broadcast::Sender<Vec<u8>>,
+    ) {
+        if let Some(apps) = apps {
+            if self.synced_app_id.as_ref() != Some(&window.app_id) {
+                send_data(window, apps, data_sender);
+                self.synced_app_id = Some(window.app_id.clone());
+            }
+        }
+
+        // title changes are reported too, as rules can depend on it
+        if let Some(app_layers) = app_layers {
+            let layer = get_layer(window, app_layers);
+            if self.synced_layer != Some(layer) {
+                send_layer(layer, data_sender);
+                self.synced_layer = Some(layer);
+            }
+        }
+    }
+}
+
+pub struct FocusedAppProvider {
+    data_sender: broadcast::Sender<Vec<u8>>,
+    is_started: Arc<AtomicBool>,
+    apps: Option<Vec<String>>,
+    app_layers: Option<AppLayersConfig>,
+}
+
+impl FocusedAppProvider {
+    pub fn new(
+        data_sender: broadcast::Sender<Vec<u8>>,
+        apps: Option<Vec<String>>,
+        app_layers: Option<AppLayersConfig>,
+    ) -> Box<dyn Provider> {
+        let provider = FocusedAppProvider {
+            data_sender,
+            is_started: Arc::new(AtomicBool::new(false)),
+            apps,
+            app_layers,
+        };
+        return Box::new(provider);
+    }
+}
+
+impl Provider for FocusedAppProvider {
+    fn start(&self) {
+        tracing::info!("Focused App Provider started");
+        self.is_started.store(true, Relaxed);
+        let data_sender = self.data_sender.clone();
+        let is_started = self.is_started.clone();
+        let apps = self.apps.clone();
+        let app_layers = self.app_layers.clone();
+        std::thread::spawn(move || {
+            let mut focus_state = FocusState::default();
+            let mut on_focus = |window: FocusedWindow| {
+                focus_state.send_changes(&window, apps.as_deref(), app_layers.as_ref(), &data_sender);
+            };
+
+            // focused window is only exposed by compositors with their own IPC, other sessions are expected to run on X11
+            if std::env::var_os("SWAYSOCK").is_some() {
+                sway::run(&is_started, &mut on_focus);
+            } else if std::env::var_os("HYPRLAND_INSTANCE_SIGNATURE").is_some() {
+                hyprland::run(&is_started, &mut on_focus);
+            } else {
+                x11::run(&is_started, &mut on_focus);
+            }
+
+            tracing::info!("Focused App Provider stopped");
+        });
+    }
+
+    fn stop(&self) {
+        self.is_started.store(false, Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_rules_with_title_patterns() {
+        let config = serde_json::from_str::<AppLayersConfig>(
+            r#"{"defaultLayer": 1, "rules": [{"app": "firefox", "title": "YouTube", "layer": 2}, {"app": "firefox", "layer": 3}]}"#,
+        )
+        .unwrap();
+        let window = |title: &str| FocusedWindow {
+            app_id: "Firefox".to_string(),
+            title: title.to_string(),
+            pid: None,
+        };
+        assert_eq!(get_layer(&window("Music - YouTube"), &config), 2);
+        assert_eq!(get_layer(&window("Docs"), &config), 3);
+        assert_eq!(get_layer(&FocusedWindow::default(), &config), 1);
+
+        let error = serde_json::from_str::<AppLayersConfig>(r#"{"rules": [{"title": "[", "layer": 2}]}"#).err();
+        assert!(error.is_some_and(|x| x.to_string().contains("unclosed character class")));
+    }
+
+    #[test]
+    fn sends_app_and_layer_of_focus_change() {
+        let config = serde_json::from_str::<AppLayersConfig>(r#"{"rules": [{"app": "code", "layer": 2}]}"#).unwrap();
+        let apps = vec!["Firefox".to_string(), "Code".to_string()];
+        let (data_sender, mut data_receiver) = broadcast::channel(crate::HOST_TO_DEVICE_CAPACITY);
+        let window = FocusedWindow {
+            app_id: "code".to_string(),
+            title: "main.rs".to_string(),
+            pid: None,
+        };
+
+        let mut focus_state = FocusState::default();
+        focus_state.send_changes(&window, Some(&apps), Some(&config), &data_sender);
+        assert_eq!(
+            data_receiver.try_recv().unwrap(),
+            [&[DataType::FocusedApp as u8, 1, 4][..], b"code"].concat()
+        );
+        assert_eq!(data_receiver.try_recv().unwrap(), vec![DataType::SetLayer as u8, 2]);
+
+        // nothing is sent again if only the title is changed
+        let window = FocusedWindow {
+            title: "lib.rs".to_string(),
+            ..window
+        };
+        focus_state.send_changes(&window, Some(&apps), Some(&config), &data_sender);
+        assert!(data_receiver.try_recv().is_err());
+    }
+}
@@ -0,0 +1,59 @@
+use std::io::{BufRead, BufReader, ErrorKind};
+use std::os::unix::net::UnixStream;
+use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
+
+use crate::utils::hyprland_ipc::{get_socket_path, request};
+
+use super::FocusedWindow;
+
+fn get_active_window() -> Option<FocusedWindow> {
+    let window = request("j/activewindow")?;
+    return Some(FocusedWindow {
+        app_id: window["class"].as_str().unwrap_or_default().to_string(),
+        title: window["title"].as_str().unwrap_or_default().to_string(),
+        pid: window["pid"].as_i64().filter(|x| *x > 0).map(|x| x as u32),
+    });
+}
+
+pub fn run(is_started: &AtomicBool, on_focus: &mut dyn FnMut(FocusedWindow)) {
+    let stream = match get_socket_path(".socket2.sock").map(UnixStream::connect) {
+        Some(Ok(stream)) => stream,
+        Some(Err(e)) => return tracing::error!("Focused App Provider can not connect to Hyprland socket: {}", e),
+        None => return tracing::error!("Focused App Provider can not find Hyprland socket"),
+    };
+    let _ = stream.set_read_timeout(Some(std::time::Duration::from_millis(1000)));
+
+    on_focus(get_active_window().unwrap_or_default());
+
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    loop {
+        if !is_started.load(Relaxed) {
+            break;
+        }
+
+        match reader.read_line(&mut line) {
+            Ok(0) => {
+                tracing::error!("Focused App Provider lost connection to Hyprland socket");
+                break;
+            }
+            Ok(_) => {
+                // activewindow>>WINDOW_CLASS,WINDOW_TITLE - also sent when title changes, both are empty if nothing is focused.
+                // Event does not contain pid, so full window info is requested
+                if let Some((app_id, title)) = line.trim().strip_prefix("activewindow>>").and_then(|x| x.split_once(',')) {
+                    on_focus(get_active_window().unwrap_or_else(|| FocusedWindow {
+                        app_id: app_id.to_string(),
+                        title: title.to_string(),
+                        pid: None,
+                    }));
+                }
+                line.clear();
+            }
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => (),
+            Err(e) => {
+                tracing::error!("Focused App Provider lost connection to Hyprland socket: {}", e);
+                break;
+            }
+        }
+    }
+}
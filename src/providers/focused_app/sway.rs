@@ -0,0 +1,62 @@
+use serde_json::Value;
+use std::io::ErrorKind;
+use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
+
+use crate::utils::sway_ipc::{connect, read_message, send_message, GET_TREE, WINDOW_EVENT};
+
+use super::FocusedWindow;
+
+fn get_window(node: &Value) -> FocusedWindow {
+    // native Wayland windows have `app_id`, XWayland windows have X11 properties instead
+    let app_id = node["app_id"].as_str().or(node["window_properties"]["class"].as_str());
+    return FocusedWindow {
+        app_id: app_id.unwrap_or_default().to_string(),
+        title: node["name"].as_str().unwrap_or_default().to_string(),
+        pid: node["pid"].as_u64().map(|x| x as u32),
+    };
+}
+
+/// Focused node can be a window or an empty workspace, which has neither `app_id` nor class
+fn find_focused(node: &Value) -> Option<&Value> {
+    if node["focused"] == true {
+        return Some(node);
+    }
+
+    let children = node["nodes"].as_array().into_iter().chain(node["floating_nodes"].as_array());
+    return children.flatten().find_map(find_focused);
+}
+
+pub fn run(is_started: &AtomicBool, on_focus: &mut dyn FnMut(FocusedWindow)) {
+    let mut stream = match connect(r#"["window"]"#) {
+        Ok(stream) => stream,
+        Err(e) => return tracing::error!("Focused App Provider can not connect to Sway IPC: {}", e),
+    };
+
+    if let Err(e) = send_message(&mut stream, GET_TREE, "") {
+        return tracing::error!("Focused App Provider can not request Sway tree: {}", e);
+    }
+
+    loop {
+        if !is_started.load(Relaxed) {
+            break;
+        }
+
+        match read_message(&mut stream) {
+            Ok((GET_TREE, tree)) => on_focus(find_focused(&tree).map(get_window).unwrap_or_default()),
+            Ok((WINDOW_EVENT, event)) => {
+                let container = &event["container"];
+                match event["change"].as_str() {
+                    Some("focus") | Some("title") if container["focused"] == true => on_focus(get_window(container)),
+                    Some("close") if container["focused"] == true => on_focus(FocusedWindow::default()),
+                    _ => (),
+                }
+            }
+            Ok(_) => (),
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => (),
+            Err(e) => {
+                tracing::error!("Focused App Provider lost connection to Sway IPC: {}", e);
+                break;
+            }
+        }
+    }
+}
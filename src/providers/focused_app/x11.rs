@@ -0,0 +1,222 @@
+use std::sync::atomic::{AtomicBool, AtomicPtr, AtomicU8, Ordering::Relaxed};
+use std::sync::Mutex;
+use std::{ffi, mem, ptr};
+use x11::xlib::{
+    Atom, Display, PropertyChangeMask, PropertyNotify, Window, XCloseDisplay, XConnectionNumber, XDefaultRootWindow, XErrorEvent, XEvent,
+    XFree, XGetClassHint, XGetWindowProperty, XInternAtom, XNextEvent, XOpenDisplay, XPending, XSelectInput, XSetErrorHandler, XSync,
+    XA_CARDINAL, XA_WINDOW, XA_WM_NAME,
+};
+
+use super::FocusedWindow;
+
+type ErrorHandler = Option<unsafe extern "C" fn(*mut Display, *mut XErrorEvent) -> ffi::c_int>;
+
+/// Xlib error handler is process-wide, so only errors of the trapped display are handled here
+static TRAP_DISPLAY: AtomicPtr<Display> = AtomicPtr::new(ptr::null_mut());
+static TRAP_ERROR_CODE: AtomicU8 = AtomicU8::new(0);
+static PREVIOUS_ERROR_HANDLER: Mutex<ErrorHandler> = Mutex::new(None);
+static TRAP_LOCK: Mutex<()> = Mutex::new(());
+
+unsafe extern "C" fn trap_error(display: *mut Display, event: *mut XErrorEvent) -> ffi::c_int {
+    if display == TRAP_DISPLAY.load(Relaxed) {
+        TRAP_ERROR_CODE.store((*event).error_code, Relaxed);
+        return 0;
+    }
+
+    let previous_handler = *PREVIOUS_ERROR_HANDLER.lock().unwrap();
+    return match previous_handler {
+        Some(handler) => handler(display, event),
+        None => 0,
+    };
+}
+
+/// Windows can be destroyed between the event and the request, default handler would terminate the process.
+/// Errors of the requests are collected with `XSync` while the trap is set, returns `None` if there were any.
+fn with_error_trap<T>(display: *mut Display, requests: impl FnOnce() -> T) -> Option<T> {
+    let _lock = TRAP_LOCK.lock().unwrap();
+    TRAP_DISPLAY.store(display, Relaxed);
+    TRAP_ERROR_CODE.store(0, Relaxed);
+    let previous_handler = unsafe { XSetErrorHandler(Some(trap_error)) };
+    *PREVIOUS_ERROR_HANDLER.lock().unwrap() = previous_handler;
+
+    let result = requests();
+
+    unsafe {
+        XSync(display, 0);
+        XSetErrorHandler(previous_handler);
+    }
+    TRAP_DISPLAY.store(ptr::null_mut(), Relaxed);
+
+    let error_code = TRAP_ERROR_CODE.load(Relaxed);
+    if error_code != 0 {
+        tracing::debug!("Focused App Provider: X11 request failed with error code {}", error_code);
+        return None;
+    }
+
+    return Some(result);
+}
+
+fn get_atom(display: *mut Display, name: &ffi::CStr) -> Atom {
+    return unsafe { XInternAtom(display, name.as_ptr(), 0) };
+}
+
+fn get_property(display: *mut Display, window: Window, property: Atom, property_type: Atom, length: ffi::c_long) -> Option<Vec<u8>> {
+    let (mut actual_type, mut actual_format, mut item_count, mut bytes_after) = (0, 0, 0, 0);
+    let mut value = ptr::null_mut();
+    let status = unsafe {
+        XGetWindowProperty(
+            display,
+            window,
+            property,
+            0,
+            length,
+            0,
+            property_type,
+            &mut actual_type,
+            &mut actual_format,
+            &mut item_count,
+            &mut bytes_after,
+            &mut value,
+        )
+    };
+    if status != 0 || value.is_null() {
+        return None;
+    }
+
+    // 32-bit items are stored as longs
+    let item_size = match actual_format {
+        32 => mem::size_of::<ffi::c_long>(),
+        16 => 2,
+        _ => 1,
+    };
+    let data = unsafe { std::slice::from_raw_parts(value, item_count as usize * item_size) }.to_vec();
+    unsafe { XFree(value as *mut ffi::c_void) };
+    return (item_count > 0).then_some(data);
+}
+
+fn get_active_window(display: *mut Display, root: Window, active_window_atom: Atom) -> Option<Window> {
+    let data = get_property(display, root, active_window_atom, XA_WINDOW, 1)?;
+    let window = data.get(..mem::size_of::<Window>())?.try_into().map(Window::from_ne_bytes).ok()?;
+    return (window != 0).then_some(window);
+}
+
+fn get_class(display: *mut Display, window: Window) -> String {
+    let mut hint = unsafe { mem::zeroed::<x11::xlib::XClassHint>() };
+    if unsafe { XGetClassHint(display, window, &mut hint) } == 0 {
+        return "".to_string();
+    }
+
+    let class = unsafe { ffi::CStr::from_ptr(hint.res_class) }.to_string_lossy().to_string();
+    unsafe {
+        XFree(hint.res_name as *mut ffi::c_void);
+        XFree(hint.res_class as *mut ffi::c_void);
+    }
+    return class;
+}
+
+struct Atoms {
+    active_window: Atom,
+    name: Atom,
+    pid: Atom,
+    utf8_string: Atom,
+}
+
+/// `_NET_WM_NAME` is UTF-8, legacy `WM_NAME` is used for windows which don't set it
+fn get_title(display: *mut Display, window: Window, atoms: &Atoms) -> String {
+    let title = get_property(display, window, atoms.name, atoms.utf8_string, 1024)
+        .or_else(|| get_property(display, window, XA_WM_NAME, 0, 1024))
+        .unwrap_or_default();
+    return String::from_utf8_lossy(&title).to_string();
+}
+
+fn get_pid(display: *mut Display, window: Window, atoms: &Atoms) -> Option<u32> {
+    let data = get_property(display, window, atoms.pid, XA_CARDINAL, 1)?;
+    let pid = data
+        .get(..mem::size_of::<ffi::c_long>())?
+        .try_into()
+        .map(ffi::c_long::from_ne_bytes)
+        .ok()?;
+    return Some(pid as u32);
+}
+
+/// Waits until X server sends something or timeout is reached, so the provider can be stopped without idle wakeups
+fn wait_for_events(display: *mut Display, timeout: i32) {
+    let mut fd = libc::pollfd {
+        fd: unsafe { XConnectionNumber(display) },
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    unsafe { libc::poll(&mut fd, 1, timeout) };
+}
+
+pub fn run(is_started: &AtomicBool, on_focus: &mut dyn FnMut(FocusedWindow)) {
+    let display = unsafe { XOpenDisplay(ptr::null()) };
+    if display.is_null() {
+        return tracing::error!("Focused App Provider can not open X11 display");
+    }
+
+    let atoms = Atoms {
+        active_window: get_atom(display, c"_NET_ACTIVE_WINDOW"),
+        name: get_atom(display, c"_NET_WM_NAME"),
+        pid: get_atom(display, c"_NET_WM_PID"),
+        utf8_string: get_atom(display, c"UTF8_STRING"),
+    };
+
+    // root window property changes when focus moves, active window property changes when its title changes
+    let root = unsafe { XDefaultRootWindow(display) };
+    unsafe { XSelectInput(display, root, PropertyChangeMask) };
+    let mut active_window: Option<Window> = None;
+    let mut is_changed = true;
+
+    loop {
+        if !is_started.load(Relaxed) {
+            break;
+        }
+
+        while unsafe { XPending(display) } > 0 {
+            let mut event = unsafe { mem::zeroed::<XEvent>() };
+            unsafe { XNextEvent(display, &mut event) };
+            if event.get_type() != PropertyNotify {
+                continue;
+            }
+
+            let property_event = unsafe { event.property };
+            is_changed |= (property_event.window == root && property_event.atom == atoms.active_window)
+                || (Some(property_event.window) == active_window && [atoms.name, XA_WM_NAME].contains(&property_event.atom));
+        }
+
+        if is_changed {
+            is_changed = false;
+            let window = get_active_window(display, root, atoms.active_window);
+            if window != active_window {
+                if let Some(previous) = active_window {
+                    with_error_trap(display, || unsafe { XSelectInput(display, previous, 0) });
+                }
+                if let Some(window) = window {
+                    with_error_trap(display, || unsafe { XSelectInput(display, window, PropertyChangeMask) });
+                }
+                active_window = window;
+            }
+
+            // window destroyed meanwhile is skipped, the next active window change is reported instead
+            let focused_window = match window {
+                Some(window) => with_error_trap(display, || FocusedWindow {
+                    app_id: get_class(display, window),
+                    title: get_title(display, window, &atoms),
+                    pid: get_pid(display, window, &atoms),
+                }),
+                None => Some(FocusedWindow::default()),
+            };
+            if let Some(focused_window) = focused_window {
+                on_focus(focused_window);
+            }
+        }
+
+        // property requests can read new events into the queue, they are handled without waiting
+        if unsafe { XPending(display) } == 0 {
+            wait_for_events(display, 1000);
+        }
+    }
+
+    unsafe { XCloseDisplay(display) };
+}
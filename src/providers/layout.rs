@@ -15,3 +15,127 @@ mod macos;
 
 #[cfg(target_os = "macos")]
 pub use self::macos::LayoutProvider;
+
+use crate::config::{get_config, LayoutConfig};
+use crate::data_type::DataType;
+
+/// Strips variant from xkb-style names (`us(intl)` is `us`), full names like `English (US)` are kept as is
+fn get_base_layout(value: &str) -> &str {
+    return match value.strip_suffix(')').and_then(|x| x.split_once('(')) {
+        Some((base, _)) if !base.is_empty() && !base.contains(char::is_whitespace) => base,
+        _ => value,
+    };
+}
+
+/// Finds index of the layout in config: ids and aliases first, then patterns, then the base layout
+/// without variant (`us(dvorak)` matches `us`).
+pub fn get_layout_index(value: &str, layouts: &[LayoutConfig]) -> Option<usize> {
+    let base_value = get_base_layout(value);
+
+    let index = layouts
+        .iter()
+        .position(|x| x.has_name(value))
+        .or_else(|| {
+            layouts
+                .iter()
+                .position(|x| x.patterns().iter().any(|pattern| pattern.is_match(value)))
+        })
+        .or_else(|| layouts.iter().position(|x| x.has_name(base_value)));
+
+    if index.is_none() {
+        tracing::warn!(
+            "layout '{}' does not match any configured layout, add it to `layouts` as an id or alias to use it",
+            value
+        );
+    }
+
+    return index;
+}
+
+/// Name is taken from `label` of the matched layout, otherwise matched id or detected layout name
+/// without variant is used in upper case (`de` is sent as `DE`).
+fn get_layout_name(value: &str, index: Option<usize>, layouts: &[LayoutConfig]) -> String {
+    let layout = index.and_then(|x| layouts.get(x));
+    return match layout.and_then(|x| x.label()) {
+        Some(label) => label.to_string(),
+        None => {
+            let name = layout.map(|x| x.id()).unwrap_or(value);
+            get_base_layout(name).to_uppercase()
+        }
+    };
+}
+
+/// Returns layout frame with matched index or configured fallback, and layout name frame if it is enabled.
+/// Fallback only affects the index, name frame always describes the detected layout.
+pub fn get_layout_data(value: &str, layouts: &[LayoutConfig]) -> Vec<Vec<u8>> {
+    let config = get_config();
+    return build_layout_data(value, layouts, config.layout_fallback, config.layout_name.unwrap_or(false));
+}
+
+fn build_layout_data(value: &str, layouts: &[LayoutConfig], fallback: Option<u8>, is_name_enabled: bool) -> Vec<Vec<u8>> {
+    let index = get_layout_index(value, layouts);
+    let mut result = vec![];
+
+    if let Some(index) = index.or(fallback.map(|x| x as usize)) {
+        result.push(vec![DataType::Layout as u8, index as u8]);
+    }
+
+    if is_name_enabled {
+        let mut data = get_layout_name(value, index, layouts).into_bytes();
+        data.truncate(30);
+        data.insert(0, data.len() as u8);
+        data.insert(0, DataType::LayoutName as u8);
+        result.push(data);
+    }
+
+    return result;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_only_xkb_variants() {
+        assert_eq!(get_base_layout("us(intl)"), "us");
+        assert_eq!(get_base_layout("de(nodeadkeys)"), "de");
+        assert_eq!(get_base_layout("us"), "us");
+        assert_eq!(get_base_layout("English (US)"), "English (US)");
+        assert_eq!(get_base_layout("German (no dead keys)"), "German (no dead keys)");
+        assert_eq!(get_base_layout("(intl)"), "(intl)");
+    }
+
+    #[test]
+    fn rejects_invalid_patterns() {
+        let layouts = serde_json::from_str::<Vec<LayoutConfig>>(r#"["en", {"id": "de", "patterns": ["^German"]}]"#).unwrap();
+        assert!(layouts[1].patterns()[0].is_match("German (no dead keys)"));
+
+        let error = serde_json::from_str::<Vec<LayoutConfig>>(r#"["en", {"id": "de", "patterns": ["German ("]}]"#).err();
+        assert!(error.is_some_and(|x| x.to_string().contains("unclosed group")));
+    }
+
+    #[test]
+    fn sends_fallback_index_with_detected_layout_name() {
+        let layouts =
+            serde_json::from_str::<Vec<LayoutConfig>>(r#"[{"id": "en", "label": "EN"}, {"id": "de", "aliases": ["German"]}]"#).unwrap();
+        assert_eq!(get_layout_index("en", &layouts), Some(0));
+        assert_eq!(get_layout_index("de(nodeadkeys)", &layouts), Some(1));
+        assert_eq!(get_layout_index("fr", &layouts), None);
+
+        assert_eq!(get_layout_name("en", Some(0), &layouts), "EN");
+        assert_eq!(get_layout_name("German", Some(1), &layouts), "DE");
+        assert_eq!(get_layout_name("fr(azerty)", None, &layouts), "FR");
+
+        // unmatched layout is sent with the fallback index, but its name is the detected one
+        let (layout, layout_name) = (DataType::Layout as u8, DataType::LayoutName as u8);
+        assert_eq!(
+            build_layout_data("fr(azerty)", &layouts, Some(0), true),
+            vec![vec![layout, 0], vec![layout_name, 2, b'F', b'R']]
+        );
+        assert_eq!(
+            build_layout_data("fr(azerty)", &layouts, None, true),
+            vec![vec![layout_name, 2, b'F', b'R']]
+        );
+        assert_eq!(build_layout_data("German", &layouts, Some(0), false), vec![vec![layout, 1]]);
+    }
+}
@@ -1,44 +1,110 @@
+mod gnome;
+mod hyprland;
+mod kde;
+mod leds;
+mod sway;
+mod x11;
+
+use std::io;
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd};
 use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
-use std::sync::Arc;
-use std::{ffi, mem, ptr};
+use std::sync::{Arc, Mutex};
 use tokio::sync::broadcast;
-use x11::xlib::{XGetAtomName, XOpenDisplay, XkbAllocKeyboard, XkbGetNames, XkbGetState, _XDisplay, _XkbDesc, _XkbStateRec};
 
-use crate::config::get_config;
+use crate::config::{get_config, LayoutBackend, LayoutConfig};
 use crate::data_type::DataType;
 
 use super::super::_base::Provider;
+use super::get_layout_data;
 
-fn get_symbols(display: *mut _XDisplay, keyboard: *mut _XkbDesc) -> String {
-    unsafe { XkbGetNames(display, 1 << 2, keyboard) };
-    let symbols_atom = unsafe { keyboard.read().names.read().symbols };
-    let symbols_ptr = unsafe { XGetAtomName(display, symbols_atom) };
-    let symbols_cstr = unsafe { ffi::CStr::from_ptr(symbols_ptr) };
-    let symbols = String::from_utf8(symbols_cstr.to_bytes().to_vec()).unwrap_or_default();
+fn send_data(value: &str, layouts: &[LayoutConfig], data_sender: &broadcast::Sender<Vec<u8>>) {
+    tracing::info!(
+        "new layout: '{0}', layout list: {1:?}",
+        value,
+        layouts.iter().map(|x| x.id()).collect::<Vec<_>>()
+    );
+    for data in get_layout_data(value, layouts) {
+        if let Err(e) = data_sender.send(data) {
+            tracing::error!("failed to send layout data: {}", e);
+        }
+    }
+}
 
-    tracing::info!("layout symbols: {}", symbols);
+const NUM_LOCK: u8 = 1 << 0;
+const CAPS_LOCK: u8 = 1 << 1;
+const SCROLL_LOCK: u8 = 1 << 2;
 
-    return symbols;
+/// Lock state bits match QMK `led_t`, so firmware can use it directly
+fn send_lock_state(value: u8, data_sender: &broadcast::Sender<Vec<u8>>) {
+    tracing::info!("new lock state: {:#05b}", value);
+    let data = vec![DataType::LockState as u8, value];
+    if let Err(e) = data_sender.send(data) {
+        tracing::error!("failed to send lock state data: {}", e);
+    }
 }
 
-fn get_layout_index(display: *mut _XDisplay) -> usize {
-    let mut state = unsafe { mem::zeroed::<_XkbStateRec>() };
-    unsafe { XkbGetState(display, 0x0100, &mut state) };
-    return state.group as usize;
+/// Returns read and write ends of a pipe, closing the write end wakes up everything polling the read end
+fn create_stop_pipe() -> io::Result<(OwnedFd, OwnedFd)> {
+    let mut fds = [0; 2];
+    if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    return Ok(unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) });
 }
 
-fn send_data(value: &String, layouts: &Vec<String>, data_sender: &broadcast::Sender<Vec<u8>>) {
-    tracing::info!("new layout: '{0}', layout list: {1:?}", value, layouts);
-    let index = layouts.into_iter().position(|r| r == value);
-    if let Some(index) = index {
-        let data = vec![DataType::Layout as u8, index as u8];
-        data_sender.send(data).unwrap();
+/// Blocks until the backend has something to read or the provider is stopped, returns `false` when stopped.
+/// Stop pipe is closed on stop, so backends exit right away and do not outlive a quick restart of providers.
+fn wait_for_input(fd: BorrowedFd, stop_fd: BorrowedFd) -> bool {
+    let mut fds = [
+        libc::pollfd {
+            fd: fd.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        },
+        libc::pollfd {
+            fd: stop_fd.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        },
+    ];
+    if unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, -1) } < 0 {
+        return io::Error::last_os_error().kind() == io::ErrorKind::Interrupted;
     }
+
+    return fds[1].revents == 0;
+}
+
+/// Detects backend from the session environment, X11 is used as a fallback
+fn detect_backend() -> LayoutBackend {
+    let has_env = |name: &str| std::env::var_os(name).is_some_and(|x| !x.is_empty());
+    let desktop = std::env::var("XDG_CURRENT_DESKTOP").unwrap_or_default().to_uppercase();
+    let is_wayland = has_env("WAYLAND_DISPLAY") || std::env::var("XDG_SESSION_TYPE").is_ok_and(|x| x == "wayland");
+
+    if has_env("SWAYSOCK") {
+        return LayoutBackend::Sway;
+    }
+
+    if has_env("HYPRLAND_INSTANCE_SIGNATURE") {
+        return LayoutBackend::Hyprland;
+    }
+
+    if is_wayland && desktop.contains("KDE") {
+        return LayoutBackend::Kde;
+    }
+
+    if is_wayland && desktop.contains("GNOME") {
+        return LayoutBackend::Gnome;
+    }
+
+    return LayoutBackend::X11;
 }
 
 pub struct LayoutProvider {
     data_sender: broadcast::Sender<Vec<u8>>,
     is_started: Arc<AtomicBool>,
+    /// Dropped on stop to wake up the backend event loop
+    stop_sender: Mutex<Option<OwnedFd>>,
 }
 
 impl LayoutProvider {
@@ -46,6 +112,7 @@ impl LayoutProvider {
         let provider = LayoutProvider {
             data_sender,
             is_started: Arc::new(AtomicBool::new(false)),
+            stop_sender: Mutex::new(None),
         };
         return Box::new(provider);
     }
@@ -54,31 +121,62 @@ impl LayoutProvider {
 impl Provider for LayoutProvider {
     fn start(&self) {
         tracing::info!("Layout Provider started");
+        let (stop_receiver, stop_sender) = match create_stop_pipe() {
+            Ok(pipe) => pipe,
+            Err(e) => return tracing::error!("Layout Provider can not create stop pipe: {}", e),
+        };
+        *self.stop_sender.lock().unwrap() = Some(stop_sender);
         self.is_started.store(true, Relaxed);
         let layouts = &get_config().layouts;
+        let backend = match get_config().layout_backend.unwrap_or_default() {
+            LayoutBackend::Auto => detect_backend(),
+            backend => backend,
+        };
+        let is_lock_state_enabled = get_config().lock_state.unwrap_or(false);
+
+        // X11 backend reports lock state using the same display, for other backends keyboard LEDs are read from sysfs
+        if is_lock_state_enabled && !matches!(backend, LayoutBackend::X11) {
+            let data_sender = self.data_sender.clone();
+            let is_started = self.is_started.clone();
+            match stop_receiver.try_clone() {
+                Ok(stop_receiver) => {
+                    std::thread::spawn(move || {
+                        leds::run(&is_started, stop_receiver.as_fd(), &mut |lock_state| {
+                            send_lock_state(lock_state, &data_sender)
+                        });
+                    });
+                }
+                Err(e) => tracing::error!("Layout Provider can not create stop pipe for lock state: {}", e),
+            }
+        }
+
         let data_sender = self.data_sender.clone();
         let is_started = self.is_started.clone();
         std::thread::spawn(move || {
-            let mut synced_layout = 0;
-            let display = unsafe { XOpenDisplay(ptr::null()) };
-            let keyboard = unsafe { XkbAllocKeyboard() };
-            let symbols = get_symbols(display, keyboard);
-            let symbol_list = symbols.split('+').map(|x| x.to_string()).collect::<Vec<String>>();
-
-            loop {
-                if !is_started.load(Relaxed) {
-                    break;
-                }
-
-                let layout = get_layout_index(display);
+            tracing::info!("Layout Provider uses {:?} backend", backend);
+            let mut synced_layout = "".to_string();
+            let mut on_layout = |layout: String| {
                 if synced_layout != layout {
                     synced_layout = layout;
-                    let layout_symbol = symbol_list.get(layout + 1).map(|x| x.to_string()).unwrap_or_default();
-                    let layout_name = layout_symbol.split([':', '(']).next().unwrap_or_default().to_string();
-                    send_data(&layout_name, layouts, &data_sender);
+                    send_data(&synced_layout, layouts, &data_sender);
                 }
+            };
 
-                std::thread::sleep(std::time::Duration::from_millis(100));
+            match backend {
+                LayoutBackend::Sway => sway::run(&is_started, stop_receiver.as_fd(), &mut on_layout),
+                LayoutBackend::Hyprland => hyprland::run(&is_started, stop_receiver.as_fd(), &mut on_layout),
+                LayoutBackend::Kde => kde::run(&is_started, stop_receiver.as_fd(), &mut on_layout),
+                LayoutBackend::Gnome => gnome::run(&is_started, stop_receiver.as_fd(), &mut on_layout),
+                _ => {
+                    let mut on_lock_state = |lock_state| send_lock_state(lock_state, &data_sender);
+                    let x11_on_lock_state = is_lock_state_enabled.then_some(&mut on_lock_state as &mut dyn FnMut(u8));
+                    let is_x11_started = x11::run(&is_started, stop_receiver.as_fd(), &mut on_layout, x11_on_lock_state);
+                    // Without display (e.g. on a console or unsupported Wayland compositor) lock state is still available from sysfs
+                    if !is_x11_started && is_lock_state_enabled {
+                        tracing::info!("Layout Provider reads lock state from keyboard LEDs");
+                        leds::run(&is_started, stop_receiver.as_fd(), &mut on_lock_state);
+                    }
+                }
             }
 
             tracing::info!("Layout Provider stopped");
@@ -87,5 +185,6 @@ impl Provider for LayoutProvider {
 
     fn stop(&self) {
         self.is_started.store(false, Relaxed);
+        self.stop_sender.lock().unwrap().take();
     }
 }
@@ -0,0 +1,74 @@
+use std::io::{BufRead, BufReader};
+use std::os::fd::{AsFd, BorrowedFd};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
+
+const SCHEMA: &str = "org.gnome.desktop.input-sources";
+
+/// GNOME keeps input sources in most recently used order, so the first one is the current:
+/// `[('xkb', 'us'), ('xkb', 'de')]`
+fn parse_current_source(value: &str) -> Option<String> {
+    let source = value.split("('").nth(1)?;
+    let id = source.split('\'').nth(2)?;
+    // variants are written as `us+dvorak`, X11 style `us(dvorak)` is used for matching
+    return match id.split_once('+') {
+        Some((layout, variant)) => Some(format!("{}({})", layout, variant)),
+        None => Some(id.to_string()),
+    };
+}
+
+fn get_current_source() -> Option<String> {
+    let output = Command::new("gsettings").args(["get", SCHEMA, "mru-sources"]).output().ok()?;
+    return parse_current_source(&String::from_utf8_lossy(&output.stdout));
+}
+
+pub fn run(is_started: &AtomicBool, stop_fd: BorrowedFd, on_layout: &mut dyn FnMut(String)) {
+    let mut child = match Command::new("gsettings")
+        .args(["monitor", SCHEMA, "mru-sources"])
+        .stdout(Stdio::piped())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(e) => return tracing::error!("Layout Provider can not start gsettings: {}", e),
+    };
+
+    if let Some(layout) = get_current_source() {
+        on_layout(layout);
+    }
+
+    if let Some(stdout) = child.stdout.take() {
+        let mut reader = BufReader::new(stdout);
+        let mut line = String::new();
+        loop {
+            if !is_started.load(Relaxed) {
+                break;
+            }
+
+            // buffered lines are handled before waiting for more output
+            if reader.buffer().is_empty() && !super::wait_for_input(reader.get_ref().as_fd(), stop_fd) {
+                break;
+            }
+
+            match reader.read_line(&mut line) {
+                Ok(0) => {
+                    tracing::error!("Layout Provider: gsettings monitor exited");
+                    break;
+                }
+                // mru-sources: [('xkb', 'de'), ('xkb', 'us')]
+                Ok(_) => {
+                    if let Some(layout) = parse_current_source(&line) {
+                        on_layout(layout);
+                    }
+                    line.clear();
+                }
+                Err(e) => {
+                    tracing::error!("Layout Provider can not read gsettings monitor: {}", e);
+                    break;
+                }
+            }
+        }
+    }
+
+    let _ = child.kill();
+    let _ = child.wait();
+}
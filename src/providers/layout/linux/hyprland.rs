@@ -0,0 +1,58 @@
+use std::io::{BufRead, BufReader, ErrorKind};
+use std::os::fd::{AsFd, BorrowedFd};
+use std::os::unix::net::UnixStream;
+use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
+
+use crate::utils::hyprland_ipc::{get_socket_path, request};
+
+fn get_active_layout() -> Option<String> {
+    let devices = request("j/devices")?;
+    let keyboards = devices["keyboards"].as_array()?;
+    let keyboard = keyboards.iter().find(|x| x["main"] == true).or(keyboards.first())?;
+    return keyboard["active_keymap"].as_str().map(|x| x.to_string());
+}
+
+pub fn run(is_started: &AtomicBool, stop_fd: BorrowedFd, on_layout: &mut dyn FnMut(String)) {
+    let stream = match get_socket_path(".socket2.sock").map(UnixStream::connect) {
+        Some(Ok(stream)) => stream,
+        Some(Err(e)) => return tracing::error!("Layout Provider can not connect to Hyprland socket: {}", e),
+        None => return tracing::error!("Layout Provider can not find Hyprland socket"),
+    };
+    let _ = stream.set_read_timeout(Some(std::time::Duration::from_millis(1000)));
+
+    if let Some(layout) = get_active_layout() {
+        on_layout(layout);
+    }
+
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    loop {
+        if !is_started.load(Relaxed) {
+            break;
+        }
+
+        // buffered events are handled before waiting for more of them
+        if reader.buffer().is_empty() && !super::wait_for_input(reader.get_ref().as_fd(), stop_fd) {
+            break;
+        }
+
+        match reader.read_line(&mut line) {
+            Ok(0) => {
+                tracing::error!("Layout Provider lost connection to Hyprland socket");
+                break;
+            }
+            Ok(_) => {
+                // activelayout>>KEYBOARD_NAME,LAYOUT_NAME
+                if let Some((_, layout)) = line.trim().strip_prefix("activelayout>>").and_then(|x| x.split_once(',')) {
+                    on_layout(layout.to_string());
+                }
+                line.clear();
+            }
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => (),
+            Err(e) => {
+                tracing::error!("Layout Provider lost connection to Hyprland socket: {}", e);
+                break;
+            }
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use dbus::blocking::LocalConnection;
+use dbus::channel::{BusType, Channel};
+use dbus::message::MatchRule;
+use std::cell::Cell;
+use std::os::fd::BorrowedFd;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
+use std::time::Duration;
+
+const SERVICE: &str = "org.kde.keyboard";
+const PATH: &str = "/Layouts";
+const INTERFACE: &str = "org.kde.KeyboardLayouts";
+
+/// Returns short name with variant (e.g. `us` or `us(dvorak)`) of the current layout
+fn get_layout(connection: &LocalConnection) -> Result<Option<String>, dbus::Error> {
+    let proxy = connection.with_proxy(SERVICE, PATH, Duration::from_millis(5000));
+    let (layouts,): (Vec<(String, String, String)>,) = proxy.method_call(INTERFACE, "getLayoutsList", ())?;
+    let (index,): (u32,) = proxy.method_call(INTERFACE, "getLayout", ())?;
+    let layout = layouts.into_iter().nth(index as usize).map(|(name, variant, _)| {
+        if variant.is_empty() {
+            name
+        } else {
+            format!("{}({})", name, variant)
+        }
+    });
+    return Ok(layout);
+}
+
+/// Connection fd is watched, so the provider can wait for signals and the stop pipe at once
+fn connect() -> Result<LocalConnection, dbus::Error> {
+    let mut channel = Channel::get_private(BusType::Session)?;
+    channel.set_watch_enabled(true);
+    return Ok(LocalConnection::from(channel));
+}
+
+pub fn run(is_started: &AtomicBool, stop_fd: BorrowedFd, on_layout: &mut dyn FnMut(String)) {
+    let connection = match connect() {
+        Ok(connection) => connection,
+        Err(e) => return tracing::error!("Layout Provider can not connect to D-Bus: {}", e),
+    };
+
+    let is_changed = Rc::new(Cell::new(true));
+    for signal in ["layoutChanged", "layoutListChanged"] {
+        let is_changed = is_changed.clone();
+        let rule = MatchRule::new_signal(INTERFACE, signal).with_path(PATH);
+        if let Err(e) = connection.add_match(rule, move |_: (), _, _| {
+            is_changed.set(true);
+            true
+        }) {
+            return tracing::error!("Layout Provider can not subscribe to KDE layout changes: {}", e);
+        }
+    }
+
+    loop {
+        if !is_started.load(Relaxed) {
+            break;
+        }
+
+        if is_changed.replace(false) {
+            match get_layout(&connection) {
+                Ok(Some(layout)) => on_layout(layout),
+                Ok(None) => (),
+                Err(e) => tracing::error!("Layout Provider can not get KDE layout: {}", e),
+            }
+        }
+
+        // messages which are already read (e.g. while waiting for a method reply) are handled first
+        match connection.process(Duration::ZERO) {
+            Ok(true) => continue,
+            Ok(false) => (),
+            Err(e) => {
+                tracing::error!("Layout Provider lost connection to D-Bus: {}", e);
+                break;
+            }
+        }
+
+        // connection fd is owned by the connection, which is open until the end of the loop
+        let connection_fd = unsafe { BorrowedFd::borrow_raw(connection.channel().watch().fd) };
+        if !super::wait_for_input(connection_fd, stop_fd) {
+            break;
+        }
+    }
+}
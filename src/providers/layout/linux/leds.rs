@@ -0,0 +1,60 @@
+use std::fs;
+use std::os::fd::{AsRawFd, BorrowedFd};
+use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
+
+use super::{CAPS_LOCK, NUM_LOCK, SCROLL_LOCK};
+
+const LEDS_PATH: &str = "/sys/class/leds";
+/// sysfs does not notify about brightness changes of keyboard LEDs, so they are polled
+const POLL_INTERVAL_MS: i32 = 500;
+
+/// Lock is on if LED of any connected keyboard is on, LEDs are named like `input3::capslock`
+fn get_lock_state() -> u8 {
+    let mut lock_state = 0;
+    for entry in fs::read_dir(LEDS_PATH).into_iter().flatten().flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let flag = match name.rsplit("::").next() {
+            Some("numlock") => NUM_LOCK,
+            Some("capslock") => CAPS_LOCK,
+            Some("scrolllock") => SCROLL_LOCK,
+            _ => continue,
+        };
+
+        let brightness = fs::read_to_string(entry.path().join("brightness")).unwrap_or_default();
+        if brightness.trim().parse::<u32>().unwrap_or_default() > 0 {
+            lock_state |= flag;
+        }
+    }
+
+    return lock_state;
+}
+
+/// Sleeps for the poll interval, returns `false` as soon as the stop pipe is closed
+fn wait_for_poll(stop_fd: BorrowedFd) -> bool {
+    let mut fds = [libc::pollfd {
+        fd: stop_fd.as_raw_fd(),
+        events: libc::POLLIN,
+        revents: 0,
+    }];
+    unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, POLL_INTERVAL_MS) };
+    return fds[0].revents == 0;
+}
+
+pub fn run(is_started: &AtomicBool, stop_fd: BorrowedFd, on_lock_state: &mut dyn FnMut(u8)) {
+    let mut synced_lock_state: Option<u8> = None;
+    loop {
+        if !is_started.load(Relaxed) {
+            break;
+        }
+
+        let lock_state = get_lock_state();
+        if synced_lock_state != Some(lock_state) {
+            synced_lock_state = Some(lock_state);
+            on_lock_state(lock_state);
+        }
+
+        if !wait_for_poll(stop_fd) {
+            break;
+        }
+    }
+}
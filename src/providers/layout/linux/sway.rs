@@ -0,0 +1,57 @@
+use serde_json::Value;
+use std::io::ErrorKind;
+use std::os::fd::{AsFd, BorrowedFd};
+use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
+
+use crate::utils::sway_ipc::{connect, read_message, send_message, GET_INPUTS, INPUT_EVENT};
+
+fn get_layout(input: &Value) -> Option<String> {
+    if input["type"] != "keyboard" {
+        return None;
+    }
+
+    return input["xkb_active_layout_name"].as_str().map(|x| x.to_string());
+}
+
+pub fn run(is_started: &AtomicBool, stop_fd: BorrowedFd, on_layout: &mut dyn FnMut(String)) {
+    let mut stream = match connect(r#"["input"]"#) {
+        Ok(stream) => stream,
+        Err(e) => return tracing::error!("Layout Provider can not connect to Sway IPC: {}", e),
+    };
+
+    // events are only sent on changes, so current layout is requested explicitly
+    if let Err(e) = send_message(&mut stream, GET_INPUTS, "") {
+        return tracing::error!("Layout Provider can not request Sway inputs: {}", e);
+    }
+
+    loop {
+        if !is_started.load(Relaxed) {
+            break;
+        }
+
+        if !super::wait_for_input(stream.as_fd(), stop_fd) {
+            break;
+        }
+
+        match read_message(&mut stream) {
+            Ok((GET_INPUTS, inputs)) => {
+                if let Some(layout) = inputs.as_array().and_then(|x| x.iter().find_map(get_layout)) {
+                    on_layout(layout);
+                }
+            }
+            Ok((INPUT_EVENT, event)) => {
+                if matches!(event["change"].as_str(), Some("xkb_layout") | Some("xkb_keymap")) {
+                    if let Some(layout) = get_layout(&event["input"]) {
+                        on_layout(layout);
+                    }
+                }
+            }
+            Ok(_) => (),
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => (),
+            Err(e) => {
+                tracing::error!("Layout Provider lost connection to Sway IPC: {}", e);
+                break;
+            }
+        }
+    }
+}
@@ -0,0 +1,172 @@
+use std::os::fd::BorrowedFd;
+use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
+use std::{ffi, mem, ptr};
+use x11::xlib::{
+    _XDisplay, _XkbDesc, _XkbStateRec, XCloseDisplay, XConnectionNumber, XEvent, XFree, XGetAtomName, XInternAtom, XNextEvent,
+    XOpenDisplay, XPending, XkbAllocKeyboard, XkbAnyEvent, XkbFreeKeyboard, XkbGetNamedIndicator, XkbGetNames, XkbGetState,
+    XkbGroupStateMask, XkbIndicatorStateNotify, XkbNamesNotify, XkbNewKeyboardNotify, XkbNewKeyboardNotifyMask, XkbQueryExtension,
+    XkbSelectEventDetails, XkbStateNotify, XkbStateNotifyEvent,
+};
+
+use super::{CAPS_LOCK, NUM_LOCK, SCROLL_LOCK};
+
+const XKB_USE_CORE_KBD: u32 = 0x0100;
+const XKB_SYMBOLS_NAME_MASK: u64 = 1 << 2;
+const XKB_GROUP_NAMES_MASK: u64 = 1 << 12;
+const XKB_ALL_INDICATORS_MASK: u64 = 0xFFFFFFFF;
+
+fn get_symbols(display: *mut _XDisplay, keyboard: *mut _XkbDesc) -> Vec<String> {
+    unsafe { XkbGetNames(display, XKB_SYMBOLS_NAME_MASK as u32, keyboard) };
+    let symbols_atom = unsafe { keyboard.read().names.read().symbols };
+    let symbols_ptr = unsafe { XGetAtomName(display, symbols_atom) };
+    if symbols_ptr.is_null() {
+        return vec![];
+    }
+
+    let symbols = unsafe { ffi::CStr::from_ptr(symbols_ptr) }.to_string_lossy().to_string();
+    unsafe { XFree(symbols_ptr as *mut ffi::c_void) };
+
+    tracing::info!("layout symbols: {}", symbols);
+
+    return symbols.split('+').map(|x| x.to_string()).collect::<Vec<String>>();
+}
+
+fn get_layout_index(display: *mut _XDisplay) -> usize {
+    let mut state = unsafe { mem::zeroed::<_XkbStateRec>() };
+    unsafe { XkbGetState(display, XKB_USE_CORE_KBD, &mut state) };
+    return state.group as usize;
+}
+
+fn get_layout_name(symbol_list: &[String], layout: usize) -> String {
+    let layout_symbol = symbol_list.get(layout + 1).map(|x| x.to_string()).unwrap_or_default();
+    // `us(dvorak):2` - variant is kept for matching, group index is dropped
+    return layout_symbol.split(':').next().unwrap_or_default().to_string();
+}
+
+/// Indicators are looked up by name, as their indices depend on the keymap
+fn get_lock_state(display: *mut _XDisplay) -> u8 {
+    let mut lock_state = 0;
+    for (name, flag) in [(c"Num Lock", NUM_LOCK), (c"Caps Lock", CAPS_LOCK), (c"Scroll Lock", SCROLL_LOCK)] {
+        let atom = unsafe { XInternAtom(display, name.as_ptr(), 1) };
+        let mut is_on = 0;
+        if atom != 0
+            && unsafe { XkbGetNamedIndicator(display, atom, ptr::null_mut(), &mut is_on, ptr::null_mut(), ptr::null_mut()) } != 0
+            && is_on != 0
+        {
+            lock_state |= flag;
+        }
+    }
+
+    return lock_state;
+}
+
+/// Subscribes to group (layout) switches and keymap changes, e.g. `setxkbmap` adding a layout.
+/// Returns XKB event type, all XKB events share it and differ by `xkb_type`.
+fn select_events(display: *mut _XDisplay, with_indicators: bool) -> Option<i32> {
+    let (mut opcode, mut event_base, mut error_base) = (0, 0, 0);
+    let (mut major, mut minor) = (1, 0);
+    if unsafe { XkbQueryExtension(display, &mut opcode, &mut event_base, &mut error_base, &mut major, &mut minor) } == 0 {
+        return None;
+    }
+
+    let names_mask = XKB_SYMBOLS_NAME_MASK | XKB_GROUP_NAMES_MASK;
+    unsafe {
+        XkbSelectEventDetails(
+            display,
+            XKB_USE_CORE_KBD,
+            XkbStateNotify as u32,
+            XkbGroupStateMask,
+            XkbGroupStateMask,
+        );
+        XkbSelectEventDetails(display, XKB_USE_CORE_KBD, XkbNamesNotify as u32, names_mask, names_mask);
+        XkbSelectEventDetails(
+            display,
+            XKB_USE_CORE_KBD,
+            XkbNewKeyboardNotify as u32,
+            XkbNewKeyboardNotifyMask,
+            XkbNewKeyboardNotifyMask,
+        );
+        if with_indicators {
+            let mask = XKB_ALL_INDICATORS_MASK;
+            XkbSelectEventDetails(display, XKB_USE_CORE_KBD, XkbIndicatorStateNotify as u32, mask, mask);
+        }
+    }
+
+    return Some(event_base);
+}
+
+/// Returns `false` if X11 display or XKB extension is not available
+pub fn run(
+    is_started: &AtomicBool,
+    stop_fd: BorrowedFd,
+    on_layout: &mut dyn FnMut(String),
+    mut on_lock_state: Option<&mut dyn FnMut(u8)>,
+) -> bool {
+    let display = unsafe { XOpenDisplay(ptr::null()) };
+    if display.is_null() {
+        tracing::error!("Layout Provider can not open X11 display");
+        return false;
+    }
+
+    let xkb_event_type = match select_events(display, on_lock_state.is_some()) {
+        Some(event_type) => event_type,
+        None => {
+            unsafe { XCloseDisplay(display) };
+            tracing::error!("Layout Provider: XKB extension is not available");
+            return false;
+        }
+    };
+
+    let keyboard = unsafe { XkbAllocKeyboard() };
+    let mut symbol_list = get_symbols(display, keyboard);
+    let mut layout = get_layout_index(display);
+    on_layout(get_layout_name(&symbol_list, layout));
+    if let Some(on_lock_state) = on_lock_state.as_mut() {
+        on_lock_state(get_lock_state(display));
+    }
+
+    loop {
+        if !is_started.load(Relaxed) {
+            break;
+        }
+
+        while unsafe { XPending(display) } > 0 {
+            let mut event = unsafe { mem::zeroed::<XEvent>() };
+            unsafe { XNextEvent(display, &mut event) };
+            if event.get_type() != xkb_event_type {
+                continue;
+            }
+
+            let xkb_type = unsafe { (*(&event as *const XEvent as *const XkbAnyEvent)).xkb_type };
+            if xkb_type == XkbStateNotify {
+                let state_event = unsafe { *(&event as *const XEvent as *const XkbStateNotifyEvent) };
+                layout = state_event.group as usize;
+            } else if xkb_type == XkbIndicatorStateNotify {
+                if let Some(on_lock_state) = on_lock_state.as_mut() {
+                    on_lock_state(get_lock_state(display));
+                }
+                continue;
+            } else if xkb_type == XkbNamesNotify || xkb_type == XkbNewKeyboardNotify {
+                symbol_list = get_symbols(display, keyboard);
+                layout = get_layout_index(display);
+            } else {
+                continue;
+            }
+
+            on_layout(get_layout_name(&symbol_list, layout));
+        }
+
+        // X server connection is owned by the display, which is open until the end of the loop
+        let connection_fd = unsafe { BorrowedFd::borrow_raw(XConnectionNumber(display)) };
+        if !super::wait_for_input(connection_fd, stop_fd) {
+            break;
+        }
+    }
+
+    unsafe {
+        XkbFreeKeyboard(keyboard, 0, 1);
+        XCloseDisplay(display);
+    }
+
+    return true;
+}
@@ -5,10 +5,10 @@ use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
 use std::sync::Arc;
 use tokio::sync::broadcast;
 
-use crate::config::get_config;
-use crate::data_type::DataType;
+use crate::config::{get_config, LayoutConfig};
 
 use super::super::_base::Provider;
+use super::get_layout_data;
 
 #[link(name = "Carbon", kind = "framework")]
 extern "C" {
@@ -43,11 +43,14 @@ fn get_keyboard_layout() -> Option<String> {
     }
 }
 
-fn send_data(value: &String, layouts: &Vec<String>, data_sender: &broadcast::Sender<Vec<u8>>) {
-    tracing::info!("new layout: '{0}', layout list: {1:?}", value, layouts);
-    if let Some(index) = layouts.into_iter().position(|r| r == value) {
-        let data = vec![DataType::Layout as u8, index as u8];
-        if let Err(e) =  data_sender.send(data) {
+fn send_data(value: &str, layouts: &[LayoutConfig], data_sender: &broadcast::Sender<Vec<u8>>) {
+    tracing::info!(
+        "new layout: '{0}', layout list: {1:?}",
+        value,
+        layouts.iter().map(|x| x.id()).collect::<Vec<_>>()
+    );
+    for data in get_layout_data(value, layouts) {
+        if let Err(e) = data_sender.send(data) {
             tracing::error!("failed to send layout data: {}", e);
         }
     }
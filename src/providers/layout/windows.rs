@@ -10,10 +10,10 @@ use windows::Win32::{
     },
 };
 
-use crate::config::get_config;
-use crate::data_type::DataType;
+use crate::config::{get_config, LayoutConfig};
 
 use super::super::_base::Provider;
+use super::get_layout_data;
 
 unsafe fn get_layout() -> Option<String> {
     let focused_window = GetForegroundWindow();
@@ -23,16 +23,17 @@ unsafe fn get_layout() -> Option<String> {
     let mut layout_name_arr = [0u16; 9];
     let _ = GetLocaleInfoW(locale_id, LOCALE_SISO639LANGNAME, Some(&mut layout_name_arr));
     if let Some(trimmed_arr) = layout_name_arr.split(|&x| x == 0u16).next() {
-        return String::from_utf16(&trimmed_arr).ok();
+        return String::from_utf16(trimmed_arr).ok();
     }
 
     None
 }
 
-fn send_data(value: &String, layouts: &Vec<String>, data_sender: &broadcast::Sender<Vec<u8>>) {
-    if let Some(index) = layouts.into_iter().position(|r| r == value) {
-        let data = vec![DataType::Layout as u8, index as u8];
-        data_sender.send(data).unwrap();
+fn send_data(value: &str, layouts: &[LayoutConfig], data_sender: &broadcast::Sender<Vec<u8>>) {
+    for data in get_layout_data(value, layouts) {
+        if let Err(e) = data_sender.send(data) {
+            tracing::error!("failed to send layout data: {}", e);
+        }
     }
 }
 
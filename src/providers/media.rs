@@ -1,3 +1,6 @@
+#[cfg(not(target_os = "macos"))]
+mod command;
+
 #[cfg(target_os = "linux")]
 mod linux;
 
@@ -0,0 +1,97 @@
+use tokio::sync::{broadcast, oneshot};
+
+use crate::data_type::DataType;
+use crate::utils::command_receiver::{CommandReceiver, Received};
+
+const COMMAND_PLAY_PAUSE: u8 = 0;
+const COMMAND_NEXT: u8 = 1;
+const COMMAND_PREVIOUS: u8 = 2;
+const COMMAND_SEEK: u8 = 3;
+const COMMAND_SHUFFLE: u8 = 4;
+const COMMAND_LIKE: u8 = 5;
+
+#[derive(Debug, Clone, Copy)]
+pub enum MediaCommand {
+    PlayPause,
+    Next,
+    Previous,
+    /// Offset in seconds from the current position, negative to seek backwards
+    Seek(i16),
+    /// Toggles shuffle
+    Shuffle,
+    Like,
+}
+
+impl MediaCommand {
+    /// `[command, offset_lo, offset_hi]` - offset is used only by seek
+    fn parse(command: &[u8]) -> Option<MediaCommand> {
+        return match command.first() {
+            Some(&COMMAND_PLAY_PAUSE) => Some(MediaCommand::PlayPause),
+            Some(&COMMAND_NEXT) => Some(MediaCommand::Next),
+            Some(&COMMAND_PREVIOUS) => Some(MediaCommand::Previous),
+            Some(&COMMAND_SEEK) => Some(MediaCommand::Seek(i16::from_le_bytes([
+                *command.get(1).unwrap_or(&0),
+                *command.get(2).unwrap_or(&0),
+            ]))),
+            Some(&COMMAND_SHUFFLE) => Some(MediaCommand::Shuffle),
+            Some(&COMMAND_LIKE) => Some(MediaCommand::Like),
+            _ => None,
+        };
+    }
+}
+
+/// Commands are received in a separate thread, as waiting for player events is blocking.
+/// Returns stop sender, the thread is stopped when it is dropped.
+pub fn start_listener(device_to_host_sender: &broadcast::Sender<Vec<u8>>, handle_command: fn(MediaCommand)) -> Option<oneshot::Sender<()>> {
+    let (mut command_receiver, stop_sender) = match CommandReceiver::new(device_to_host_sender, DataType::MediaCommand) {
+        Ok(command_receiver) => command_receiver,
+        Err(e) => {
+            tracing::error!("Media Provider can not receive commands: {}", e);
+            return None;
+        }
+    };
+
+    std::thread::spawn(move || loop {
+        match command_receiver.recv(None) {
+            Received::Command(data) => match MediaCommand::parse(&data) {
+                Some(command) => {
+                    tracing::info!("media command: {:?}", command);
+                    handle_command(command);
+                }
+                None => tracing::warn!("Media Provider: unknown command {:?}", data.first()),
+            },
+            Received::Timeout => {}
+            Received::Stopped => break,
+        }
+    });
+    return Some(stop_sender);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_commands() {
+        assert!(matches!(MediaCommand::parse(&[COMMAND_PLAY_PAUSE]), Some(MediaCommand::PlayPause)));
+        assert!(matches!(MediaCommand::parse(&[COMMAND_LIKE, 0xFF]), Some(MediaCommand::Like)));
+        assert!(matches!(
+            MediaCommand::parse(&[COMMAND_SEEK, 0x0A, 0x00]),
+            Some(MediaCommand::Seek(10))
+        ));
+        // offset is i16 LE, -10 is `0xFFF6`
+        assert!(matches!(
+            MediaCommand::parse(&[COMMAND_SEEK, 0xF6, 0xFF]),
+            Some(MediaCommand::Seek(-10))
+        ));
+        assert!(matches!(
+            MediaCommand::parse(&[COMMAND_SEEK, 0x00, 0x80]),
+            Some(MediaCommand::Seek(i16::MIN))
+        ));
+        // missing offset bytes are zeros
+        assert!(matches!(MediaCommand::parse(&[COMMAND_SEEK, 0xF6]), Some(MediaCommand::Seek(246))));
+        assert!(matches!(MediaCommand::parse(&[COMMAND_SEEK]), Some(MediaCommand::Seek(0))));
+        assert!(MediaCommand::parse(&[]).is_none());
+        assert!(MediaCommand::parse(&[COMMAND_LIKE + 1]).is_none());
+    }
+}
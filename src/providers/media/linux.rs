@@ -1,12 +1,13 @@
 use mpris::{Metadata, PlayerFinder};
 use std::path::Path;
 use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
-use std::sync::Arc;
-use tokio::sync::broadcast;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{broadcast, oneshot};
 
 use crate::data_type::DataType;
 
 use super::super::_base::Provider;
+use super::command::{self, MediaCommand};
 
 fn send_media_data(
     metadata: &Metadata,
@@ -16,19 +17,19 @@ fn send_media_data(
 ) -> (String, String) {
     let (mut artist, mut title) = current.clone();
 
-    let new_artist = metadata.artists().and_then(|x| x.get(0).map(|x| x.to_string())).unwrap_or_default();
+    let new_artist = metadata.artists().and_then(|x| x.first().map(|x| x.to_string())).unwrap_or_default();
     if !new_artist.is_empty() && artist != new_artist {
         tracing::info!("new artist: {}", new_artist);
         artist = new_artist;
-        send_media_raw(DataType::MediaArtist, &artist, &data_sender);
+        send_media_raw(DataType::MediaArtist, &artist, data_sender);
     }
 
     let new_title = get_display_title(metadata, fallback_title);
     if !new_title.is_empty() && title != new_title {
         tracing::info!("new title: {}", new_title);
         title = new_title;
-        send_media_raw(DataType::MediaTitle, &title, &data_sender);
-        send_media_player_text(&title, &data_sender);
+        send_media_raw(DataType::MediaTitle, &title, data_sender);
+        send_media_player_text(&title, data_sender);
         std::thread::sleep(std::time::Duration::from_millis(50));
     }
 
@@ -139,17 +140,53 @@ fn send_media_extended(
     }
 }
 
+/// Commands are sent to the active player, like is not supported as MPRIS has no such method
+fn handle_command(command: MediaCommand) {
+    let player = match PlayerFinder::new().map(|x| x.find_active()) {
+        Ok(Ok(player)) => player,
+        _ => {
+            tracing::warn!("Media Provider: no active player for {:?}", command);
+            return;
+        }
+    };
+
+    let result = match command {
+        MediaCommand::PlayPause => player.play_pause(),
+        MediaCommand::Next => player.next(),
+        MediaCommand::Previous => player.previous(),
+        MediaCommand::Seek(seconds) => player.seek(seconds as i64 * 1_000_000),
+        MediaCommand::Shuffle => player.get_shuffle().and_then(|x| player.set_shuffle(!x)),
+        MediaCommand::Like => {
+            tracing::warn!("Media Provider: like is not supported by MPRIS");
+            return;
+        }
+    };
+
+    if let Err(e) = result {
+        tracing::error!("Media Provider failed to send {:?} to {}: {}", command, player.identity(), e);
+    }
+}
+
 pub struct MediaProvider {
     data_sender: broadcast::Sender<Vec<u8>>,
+    device_to_host_sender: broadcast::Sender<Vec<u8>>,
     is_started: Arc<AtomicBool>,
+    /// Dropped on stop to stop the command listener
+    command_stop_sender: Mutex<Option<oneshot::Sender<()>>>,
     extended: bool,
 }
 
 impl MediaProvider {
-    pub fn new(data_sender: broadcast::Sender<Vec<u8>>, extended: bool) -> Box<dyn Provider> {
+    pub fn new(
+        data_sender: broadcast::Sender<Vec<u8>>,
+        device_to_host_sender: broadcast::Sender<Vec<u8>>,
+        extended: bool,
+    ) -> Box<dyn Provider> {
         let provider = MediaProvider {
             data_sender,
+            device_to_host_sender,
             is_started: Arc::new(AtomicBool::new(false)),
+            command_stop_sender: Mutex::new(None),
             extended,
         };
         return Box::new(provider);
@@ -160,6 +197,7 @@ impl Provider for MediaProvider {
     fn start(&self) {
         tracing::info!("Media Provider started");
         self.is_started.store(true, Relaxed);
+        *self.command_stop_sender.lock().unwrap() = command::start_listener(&self.device_to_host_sender, handle_command);
         let data_sender = self.data_sender.clone();
         let is_started = self.is_started.clone();
         let extended = self.extended;
@@ -226,6 +264,7 @@ impl Provider for MediaProvider {
 
     fn stop(&self) {
         self.is_started.store(false, Relaxed);
+        self.command_stop_sender.lock().unwrap().take();
     }
 }
 
@@ -1,6 +1,6 @@
 use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
-use std::sync::Arc;
-use tokio::sync::broadcast;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{broadcast, oneshot};
 use windows::{
     Foundation::{EventRegistrationToken, TypedEventHandler},
     Media::Control::{GlobalSystemMediaTransportControlsSession, GlobalSystemMediaTransportControlsSessionManager},
@@ -9,6 +9,7 @@ use windows::{
 use crate::data_type::DataType;
 
 use super::super::_base::Provider;
+use super::command::{self, MediaCommand};
 
 fn get_manager() -> Result<GlobalSystemMediaTransportControlsSessionManager, ()> {
     return GlobalSystemMediaTransportControlsSessionManager::RequestAsync()
@@ -23,8 +24,8 @@ fn handle_session(
     let mut synced_artist = String::new();
     let mut synced_title = String::new();
     if let Some((artist, title)) = get_media_data(session) {
-        send_data(DataType::MediaArtist, &artist, &data_sender);
-        send_data(DataType::MediaTitle, &title, &data_sender);
+        send_data(DataType::MediaArtist, &artist, data_sender);
+        send_data(DataType::MediaTitle, &title, data_sender);
         synced_artist = artist;
         synced_title = title;
     }
@@ -82,16 +83,59 @@ fn send_data(data_type: DataType, value: &String, data_sender: &broadcast::Sende
     std::thread::sleep(std::time::Duration::from_millis(10));
 }
 
+/// Commands are sent to the current session, like is not supported as media controls have no such method
+fn handle_command(command: MediaCommand) {
+    let Ok(session) = get_manager().and_then(|x| {
+        x.GetCurrentSession()
+            .map_err(|e| tracing::warn!("Media Provider: no current session for {:?}: {}", command, e))
+    }) else {
+        return;
+    };
+
+    let result = match command {
+        MediaCommand::PlayPause => session.TryTogglePlayPauseAsync().and_then(|x| x.get()),
+        MediaCommand::Next => session.TrySkipNextAsync().and_then(|x| x.get()),
+        MediaCommand::Previous => session.TrySkipPreviousAsync().and_then(|x| x.get()),
+        // position is in 100-nanosecond units
+        MediaCommand::Seek(seconds) => session
+            .GetTimelineProperties()
+            .and_then(|x| x.Position())
+            .and_then(|x| session.TryChangePlaybackPositionAsync((x.Duration + seconds as i64 * 10_000_000).max(0)))
+            .and_then(|x| x.get()),
+        MediaCommand::Shuffle => session
+            .GetPlaybackInfo()
+            .and_then(|x| x.IsShuffleActive())
+            .and_then(|x| x.Value())
+            .and_then(|x| session.TryChangeShuffleActiveAsync(!x))
+            .and_then(|x| x.get()),
+        MediaCommand::Like => {
+            tracing::warn!("Media Provider: like is not supported by Windows media controls");
+            return;
+        }
+    };
+
+    match result {
+        Ok(true) => {}
+        Ok(false) => tracing::warn!("Media Provider: {:?} is not supported by the current player", command),
+        Err(e) => tracing::error!("Media Provider failed to send {:?}: {}", command, e),
+    }
+}
+
 pub struct MediaProvider {
     data_sender: broadcast::Sender<Vec<u8>>,
+    device_to_host_sender: broadcast::Sender<Vec<u8>>,
     is_started: Arc<AtomicBool>,
+    /// Dropped on stop to stop the command listener
+    command_stop_sender: Mutex<Option<oneshot::Sender<()>>>,
 }
 
 impl MediaProvider {
-    pub fn new(data_sender: broadcast::Sender<Vec<u8>>) -> Box<dyn Provider> {
+    pub fn new(data_sender: broadcast::Sender<Vec<u8>>, device_to_host_sender: broadcast::Sender<Vec<u8>>) -> Box<dyn Provider> {
         let provider = MediaProvider {
             data_sender,
+            device_to_host_sender,
             is_started: Arc::new(AtomicBool::new(false)),
+            command_stop_sender: Mutex::new(None),
         };
         return Box::new(provider);
     }
@@ -101,18 +145,19 @@ impl Provider for MediaProvider {
     fn start(&self) {
         tracing::info!("Media Provider started");
         self.is_started.store(true, Relaxed);
+        *self.command_stop_sender.lock().unwrap() = command::start_listener(&self.device_to_host_sender, handle_command);
         let data_sender = self.data_sender.clone();
         let is_started = self.is_started.clone();
         std::thread::spawn(move || {
             let mut session_token: Option<EventRegistrationToken> = None;
 
             if let Ok(manager) = get_manager() {
-                if let Some(session) = manager.GetCurrentSession().ok() {
+                if let Ok(session) = manager.GetCurrentSession() {
                     session_token = handle_session(&session, &data_sender);
                 }
 
                 let handler = TypedEventHandler::new(move |_manager: &Option<GlobalSystemMediaTransportControlsSessionManager>, _| {
-                    if let Some(session) = _manager.as_ref().unwrap().GetCurrentSession().ok() {
+                    if let Ok(session) = _manager.as_ref().unwrap().GetCurrentSession() {
                         if let Some(token) = session_token {
                             let _ = session.RemoveMediaPropertiesChanged(token);
                         }
@@ -146,5 +191,6 @@ impl Provider for MediaProvider {
 
     fn stop(&self) {
         self.is_started.store(false, Relaxed);
+        self.command_stop_sender.lock().unwrap().take();
     }
 }
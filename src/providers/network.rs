@@ -0,0 +1,172 @@
+#![cfg(target_os = "linux")]
+use std::fs;
+use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+use crate::config::NetworkConfig;
+use crate::data_type::DataType;
+
+use super::_base::Provider;
+
+const LINK_UP: u8 = 1 << 0;
+const DEFAULT_ROUTE: u8 = 1 << 1;
+const VPN: u8 = 1 << 2;
+
+/// Returns the interface of the default route from `/proc/net/route`.
+fn get_default_route_interface() -> Option<String> {
+    let routes = fs::read_to_string("/proc/net/route").ok()?;
+    return routes
+        .lines()
+        .skip(1)
+        .map(|line| line.split_whitespace().collect::<Vec<_>>())
+        .find(|columns| columns.get(1) == Some(&"00000000"))
+        .and_then(|columns| columns.first().map(|x| x.to_string()));
+}
+
+/// Returns received and transmitted bytes for the interface from `/proc/net/dev`.
+/// Returns received and transmitted bytes of the interface from `/proc/net/dev` content
+fn parse_counters(stats: &str, interface: &str) -> Option<(u64, u64)> {
+    for line in stats.lines().skip(2) {
+        let (name, values) = line.split_once(':')?;
+        if name.trim() == interface {
+            let values = values.split_whitespace().collect::<Vec<_>>();
+            let received = values.first()?.parse::<u64>().ok()?;
+            let transmitted = values.get(8)?.parse::<u64>().ok()?;
+            return Some((received, transmitted));
+        }
+    }
+
+    return None;
+}
+
+fn get_counters(interface: &str) -> Option<(u64, u64)> {
+    let stats = fs::read_to_string("/proc/net/dev").ok()?;
+    return parse_counters(&stats, interface);
+}
+
+fn is_link_up(interface: &str) -> bool {
+    let state = fs::read_to_string(format!("/sys/class/net/{}/operstate", interface)).unwrap_or_default();
+    // tun/wireguard interfaces report "unknown" while working
+    return matches!(state.trim(), "up" | "unknown");
+}
+
+fn is_vpn_up(vpn_interfaces: &[String]) -> bool {
+    if let Ok(entries) = fs::read_dir("/sys/class/net") {
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if vpn_interfaces.iter().any(|prefix| name.starts_with(prefix)) && is_link_up(&name) {
+                return true;
+            }
+        }
+    }
+
+    return false;
+}
+
+fn send_data(flags: u8, download: u32, upload: u32, host_to_device_sender: &broadcast::Sender<Vec<u8>>) {
+    let mut data = vec![DataType::Network as u8, flags];
+    data.extend_from_slice(&download.to_le_bytes());
+    data.extend_from_slice(&upload.to_le_bytes());
+    if let Err(e) = host_to_device_sender.send(data) {
+        tracing::error!("Network Provider failed to send data: {:?}", e);
+    }
+}
+
+pub struct NetworkProvider {
+    host_to_device_sender: broadcast::Sender<Vec<u8>>,
+    is_started: Arc<AtomicBool>,
+    config: NetworkConfig,
+}
+
+impl NetworkProvider {
+    pub fn new(host_to_device_sender: broadcast::Sender<Vec<u8>>, config: NetworkConfig) -> Box<dyn Provider> {
+        let provider = NetworkProvider {
+            host_to_device_sender,
+            is_started: Arc::new(AtomicBool::new(false)),
+            config,
+        };
+        return Box::new(provider);
+    }
+}
+
+impl Provider for NetworkProvider {
+    fn start(&self) {
+        tracing::info!("Network Provider started");
+        self.is_started.store(true, Relaxed);
+        let host_to_device_sender = self.host_to_device_sender.clone();
+        let is_started = self.is_started.clone();
+        let config = self.config.clone();
+        std::thread::spawn(move || {
+            let mut synced_data = (0u8, 0u32, 0u32);
+            let mut last_counters: Option<(String, u64, u64)> = None;
+            let mut last_time = std::time::Instant::now();
+            let vpn_interfaces = config.vpn_interfaces.unwrap_or_default();
+            loop {
+                if !is_started.load(Relaxed) {
+                    break;
+                }
+
+                let default_interface = get_default_route_interface();
+                let interface = config.interface.clone().or(default_interface.clone()).unwrap_or_default();
+
+                let mut flags = 0;
+                if !interface.is_empty() && is_link_up(&interface) {
+                    flags |= LINK_UP;
+                }
+                if default_interface.is_some() {
+                    flags |= DEFAULT_ROUTE;
+                }
+                if is_vpn_up(&vpn_interfaces) {
+                    flags |= VPN;
+                }
+
+                let (mut download, mut upload) = (0, 0);
+                if let Some((received, transmitted)) = get_counters(&interface) {
+                    let elapsed = last_time.elapsed().as_secs_f64().max(0.001);
+                    // counters are restarted if the interface is switched, e.g. from ethernet to wifi
+                    if let Some((_, last_received, last_transmitted)) = last_counters.as_ref().filter(|x| x.0 == interface) {
+                        download = (received.saturating_sub(*last_received) as f64 / elapsed).min(u32::MAX as f64) as u32;
+                        upload = (transmitted.saturating_sub(*last_transmitted) as f64 / elapsed).min(u32::MAX as f64) as u32;
+                    }
+                    last_counters = Some((interface, received, transmitted));
+                } else {
+                    last_counters = None;
+                }
+                last_time = std::time::Instant::now();
+
+                let data = (flags, download, upload);
+                if synced_data != data {
+                    synced_data = data;
+                    send_data(flags, download, upload, &host_to_device_sender);
+                }
+
+                std::thread::sleep(std::time::Duration::from_millis(config.interval.unwrap_or(1000)));
+            }
+
+            tracing::info!("Network Provider stopped");
+        });
+    }
+
+    fn stop(&self) {
+        self.is_started.store(false, Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_proc_net_dev() {
+        let stats = "\
+Inter-|   Receive                                                |  Transmit
+ face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets errs drop fifo colls carrier compressed
+    lo:   12345      67    0    0    0     0          0         0    12345      67    0    0    0     0       0          0
+wlp2s0: 987654321  123456    0    0    0     0          0         0 12345678   54321    0    0    0     0       0          0
+";
+        assert_eq!(parse_counters(stats, "wlp2s0"), Some((987654321, 12345678)));
+        assert_eq!(parse_counters(stats, "lo"), Some((12345, 12345)));
+        assert_eq!(parse_counters(stats, "eth0"), None);
+    }
+}
@@ -0,0 +1,293 @@
+#![cfg(target_os = "linux")]
+use dbus::channel::{BusType, Channel};
+use dbus::message::MessageType;
+use dbus::Message;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+use crate::config::NotificationsConfig;
+use crate::data_type::DataType;
+
+use super::_base::Provider;
+
+const INTERFACE: &str = "org.freedesktop.Notifications";
+
+/// Opens a private connection, as monitoring connection can not be used for anything else
+fn connect(address: Option<&str>) -> Result<Channel, dbus::Error> {
+    let channel = match address {
+        Some(address) => {
+            let mut channel = Channel::open_private(address)?;
+            channel.register()?;
+            channel
+        }
+        None => Channel::get_private(BusType::Session)?,
+    };
+
+    let rules = vec![
+        format!("type='method_call',interface='{}',member='Notify'", INTERFACE),
+        // notification id is only known from the reply, replies can not be filtered by interface
+        "type='method_return'".to_string(),
+        "type='error'".to_string(),
+        format!("type='signal',interface='{}',member='NotificationClosed'", INTERFACE),
+    ];
+    let message = Message::new_method_call(
+        "org.freedesktop.DBus",
+        "/org/freedesktop/DBus",
+        "org.freedesktop.DBus.Monitoring",
+        "BecomeMonitor",
+    )
+    .map_err(|e| dbus::Error::new_failed(&e))?
+    .append2(rules, 0u32);
+    channel.send_with_reply_and_block(message, Duration::from_millis(5000))?;
+    return Ok(channel);
+}
+
+/// Keeps notifications which were shown and not closed yet, with index of the app in config (`None` if not listed)
+#[derive(Default)]
+struct NotificationCounter {
+    pending: HashMap<(String, u32), Option<usize>>,
+    notifications: HashMap<u32, Option<usize>>,
+}
+
+impl NotificationCounter {
+    /// Returns `true` if counts were changed
+    fn handle_message(&mut self, message: &Message, apps: &[String]) -> bool {
+        let reply_key = || {
+            let (destination, reply_serial) = (message.destination(), message.get_reply_serial());
+            return destination
+                .zip(reply_serial)
+                .map(|(destination, serial)| (destination.to_string(), serial));
+        };
+
+        match message.msg_type() {
+            MessageType::MethodCall if message.member().as_deref() == Some("Notify") => {
+                // Notify(app_name, replaces_id, ...), replaced notification keeps its id and is not counted twice
+                let (sender, serial) = (message.sender(), message.get_serial());
+                if let (Some(sender), Some(serial), Ok((app_name, replaces_id))) = (sender, serial, message.read2::<String, u32>()) {
+                    if replaces_id == 0 || !self.notifications.contains_key(&replaces_id) {
+                        let index = apps.iter().position(|x| x.eq_ignore_ascii_case(&app_name));
+                        self.pending.insert((sender.to_string(), serial), index);
+                    }
+                }
+                false
+            }
+            MessageType::MethodReturn => match reply_key().and_then(|x| self.pending.remove(&x)) {
+                Some(index) => {
+                    let id = message.read1::<u32>().unwrap_or_default();
+                    self.notifications.insert(id, index);
+                    true
+                }
+                None => false,
+            },
+            // notification was rejected, e.g. by a replaced notification daemon
+            MessageType::Error => {
+                if let Some(key) = reply_key() {
+                    self.pending.remove(&key);
+                }
+                false
+            }
+            MessageType::Signal if message.member().as_deref() == Some("NotificationClosed") => {
+                let id = message.read1::<u32>().unwrap_or_default();
+                self.notifications.remove(&id).is_some()
+            }
+            _ => false,
+        }
+    }
+
+    fn get_counts(&self, apps: &[String]) -> Vec<u8> {
+        let mut counts = vec![0u8; apps.len()];
+        for index in self.notifications.values().flatten() {
+            counts[*index] = counts[*index].saturating_add(1);
+        }
+        return counts;
+    }
+}
+
+fn send_data(total: usize, counts: &[u8], data_sender: &broadcast::Sender<Vec<u8>>) {
+    tracing::info!("new notification counts: {0}, per app: {1:?}", total, counts);
+    let mut data = vec![DataType::Notifications as u8, total.min(u8::MAX as usize) as u8];
+    data.extend(counts.iter().take(30));
+    if let Err(e) = data_sender.send(data) {
+        tracing::error!("Notifications Provider failed to send data: {:?}", e);
+    }
+}
+
+pub struct NotificationsProvider {
+    data_sender: broadcast::Sender<Vec<u8>>,
+    is_started: Arc<AtomicBool>,
+    config: NotificationsConfig,
+    /// Kept between restarts, so notifications shown before stop are still counted
+    counter: Arc<Mutex<NotificationCounter>>,
+}
+
+impl NotificationsProvider {
+    pub fn new(data_sender: broadcast::Sender<Vec<u8>>, config: NotificationsConfig) -> Box<dyn Provider> {
+        let provider = NotificationsProvider {
+            data_sender,
+            is_started: Arc::new(AtomicBool::new(false)),
+            config,
+            counter: Arc::new(Mutex::new(NotificationCounter::default())),
+        };
+        return Box::new(provider);
+    }
+}
+
+impl Provider for NotificationsProvider {
+    fn start(&self) {
+        tracing::info!("Notifications Provider started");
+        self.is_started.store(true, Relaxed);
+        let data_sender = self.data_sender.clone();
+        let is_started = self.is_started.clone();
+        let config = self.config.clone();
+        let counter = self.counter.clone();
+        std::thread::spawn(move || {
+            let channel = match connect(config.address.as_deref()) {
+                Ok(channel) => channel,
+                Err(e) => return tracing::error!("Notifications Provider can not monitor D-Bus: {}", e),
+            };
+
+            // lock is taken per message, so a restarted provider does not wait for the old thread to exit
+            {
+                let mut counter = counter.lock().unwrap();
+                // replies to calls made while stopped were not seen, they would never be removed
+                counter.pending.clear();
+                send_data(counter.notifications.len(), &counter.get_counts(&config.apps), &data_sender);
+            }
+
+            loop {
+                if !is_started.load(Relaxed) {
+                    break;
+                }
+
+                match channel.blocking_pop_message(Duration::from_millis(1000)) {
+                    Ok(Some(message)) => {
+                        let mut counter = counter.lock().unwrap();
+                        if counter.handle_message(&message, &config.apps) {
+                            send_data(counter.notifications.len(), &counter.get_counts(&config.apps), &data_sender);
+                        }
+                    }
+                    Ok(None) => (),
+                    Err(e) => {
+                        tracing::error!("Notifications Provider lost connection to D-Bus: {}", e);
+                        break;
+                    }
+                }
+            }
+
+            tracing::info!("Notifications Provider stopped");
+        });
+    }
+
+    fn stop(&self) {
+        self.is_started.store(false, Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufRead;
+    use std::process::{Child, Command, Stdio};
+
+    /// Private bus, so the test does not depend on the session bus and its notification daemon
+    struct Bus {
+        daemon: Child,
+        address: String,
+    }
+
+    impl Bus {
+        fn start() -> Option<Self> {
+            let mut daemon = Command::new("dbus-daemon")
+                .args(["--session", "--nofork", "--print-address"])
+                .stdout(Stdio::piped())
+                .spawn()
+                .ok()?;
+            let mut address = String::new();
+            std::io::BufReader::new(daemon.stdout.take()?).read_line(&mut address).ok()?;
+            return Some(Bus {
+                daemon,
+                address: address.trim().to_string(),
+            });
+        }
+
+        fn connect(&self) -> Channel {
+            let mut channel = Channel::open_private(&self.address).unwrap();
+            channel.register().unwrap();
+            return channel;
+        }
+    }
+
+    impl Drop for Bus {
+        fn drop(&mut self) {
+            let _ = self.daemon.kill();
+            let _ = self.daemon.wait();
+        }
+    }
+
+    fn notify(client: &Channel, server: &Channel, app_name: &str, replaces_id: u32) -> u32 {
+        let destination = server.unique_name().unwrap();
+        let message = Message::new_method_call(destination, "/org/freedesktop/Notifications", INTERFACE, "Notify")
+            .unwrap()
+            .append2(app_name, replaces_id);
+        return client.send(message).unwrap();
+    }
+
+    /// Skips `NameAcquired` and other signals from the bus
+    fn receive_call(server: &Channel) -> Message {
+        loop {
+            let message = server.blocking_pop_message(Duration::from_millis(5000)).unwrap().unwrap();
+            if message.msg_type() == MessageType::MethodCall {
+                return message;
+            }
+        }
+    }
+
+    /// Feeds monitored messages to the counter until there are no more of them
+    fn handle_messages(monitor: &Channel, counter: &mut NotificationCounter, apps: &[String]) {
+        while let Some(message) = monitor.blocking_pop_message(Duration::from_millis(500)).unwrap() {
+            counter.handle_message(&message, apps);
+        }
+    }
+
+    #[test]
+    #[ignore = "needs dbus-daemon, run with `cargo test -- --ignored`"]
+    fn counts_notifications_on_private_bus() {
+        let bus = Bus::start().expect("dbus-daemon is not available");
+        let monitor = connect(Some(&bus.address)).unwrap();
+        let (client, server) = (bus.connect(), bus.connect());
+        let apps = vec!["Firefox".to_string(), "Slack".to_string()];
+        let mut counter = NotificationCounter::default();
+
+        notify(&client, &server, "firefox", 0);
+        notify(&client, &server, "telegram", 0);
+        notify(&client, &server, "slack", 0);
+        for id in [1u32, 2] {
+            let call = receive_call(&server);
+            server.send(call.method_return().append1(id)).unwrap();
+        }
+        let call = receive_call(&server);
+        let error_name = "org.freedesktop.DBus.Error.Failed".into();
+        server.send(call.error(&error_name, c"rejected")).unwrap();
+
+        handle_messages(&monitor, &mut counter, &apps);
+        assert_eq!(counter.notifications.len(), 2);
+        assert_eq!(counter.get_counts(&apps), vec![1, 0]);
+        assert!(counter.pending.is_empty());
+
+        // replacing notification keeps the count, closed notification is removed
+        notify(&client, &server, "firefox", 1);
+        let call = receive_call(&server);
+        server.send(call.method_return().append1(1u32)).unwrap();
+        let closed = Message::new_signal("/org/freedesktop/Notifications", INTERFACE, "NotificationClosed")
+            .unwrap()
+            .append2(2u32, 1u32);
+        server.send(closed).unwrap();
+
+        handle_messages(&monitor, &mut counter, &apps);
+        assert_eq!(counter.notifications.len(), 1);
+        assert_eq!(counter.get_counts(&apps), vec![1, 0]);
+    }
+}
@@ -0,0 +1,210 @@
+#![cfg(target_os = "linux")]
+use dbus::blocking::stdintf::org_freedesktop_dbus::Properties;
+use dbus::blocking::LocalConnection;
+use dbus::message::MatchRule;
+use std::cell::Cell;
+use std::process::Command;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc};
+
+use crate::data_type::DataType;
+
+use super::_base::Provider;
+
+const LOCKED: u8 = 1 << 0;
+const IDLE: u8 = 1 << 1;
+const DO_NOT_DISTURB: u8 = 1 << 2;
+
+const TIMEOUT: Duration = Duration::from_millis(2000);
+const SCREENSAVERS: [(&str, &str, &str); 2] = [
+    (
+        "org.freedesktop.ScreenSaver",
+        "/org/freedesktop/ScreenSaver",
+        "org.freedesktop.ScreenSaver",
+    ),
+    ("org.gnome.ScreenSaver", "/org/gnome/ScreenSaver", "org.gnome.ScreenSaver"),
+];
+const NOTIFICATIONS_PATH: &str = "/org/freedesktop/Notifications";
+const GNOME_BANNERS_KEY: &str = "/org/gnome/desktop/notifications/show-banners";
+
+fn get_session_path(connection: &LocalConnection) -> Option<dbus::Path<'static>> {
+    let proxy = connection.with_proxy("org.freedesktop.login1", "/org/freedesktop/login1", TIMEOUT);
+    // `auto` is resolved to the caller's session, or to the user's display session for services
+    let result: Result<(dbus::Path,), _> = proxy.method_call("org.freedesktop.login1.Manager", "GetSession", ("auto",));
+    return result
+        .map(|(path,)| path)
+        .map_err(|e| tracing::warn!("Session State Provider can not get logind session: {}", e))
+        .ok();
+}
+
+fn is_locked(connection: &LocalConnection, session_path: &dbus::Path) -> bool {
+    let proxy = connection.with_proxy("org.freedesktop.login1", session_path, TIMEOUT);
+    return proxy.get::<bool>("org.freedesktop.login1.Session", "LockedHint").unwrap_or(false);
+}
+
+fn is_idle(connection: &LocalConnection) -> bool {
+    return SCREENSAVERS.iter().any(|(service, path, interface)| {
+        let proxy = connection.with_proxy(*service, *path, TIMEOUT);
+        let result: Result<(bool,), _> = proxy.method_call(*interface, "GetActive", ());
+        result.is_ok_and(|(is_active,)| is_active)
+    });
+}
+
+/// GNOME DND disables notification banners
+fn are_gnome_banners_hidden() -> bool {
+    let output = Command::new("gsettings")
+        .args(["get", "org.gnome.desktop.notifications", "show-banners"])
+        .output();
+    return output.is_ok_and(|x| String::from_utf8_lossy(&x.stdout).trim() == "false");
+}
+
+/// There is no common DND API: KDE exposes `Inhibited` property of the notification server,
+/// dunst has its own `paused` property, GNOME banners are checked separately
+fn is_do_not_disturb(connection: &LocalConnection, are_banners_hidden: bool) -> bool {
+    let proxy = connection.with_proxy("org.freedesktop.Notifications", NOTIFICATIONS_PATH, TIMEOUT);
+    return are_banners_hidden
+        || proxy.get::<bool>("org.freedesktop.Notifications", "Inhibited").unwrap_or(false)
+        || proxy.get::<bool>("org.dunstproject.cmd0", "paused").unwrap_or(false);
+}
+
+/// GSettings are stored in dconf, which signals every write with the changed keys (or a changed directory),
+/// so `gsettings` is only run again when banners setting could be changed
+fn subscribe_gnome_banners(connection: &LocalConnection, is_changed: &Rc<Cell<bool>>) {
+    let is_changed = is_changed.clone();
+    let rule = MatchRule::new_signal("ca.desrt.dconf.Writer", "Notify");
+    if let Err(e) = connection.add_match(rule, move |(prefix, changes, _): (String, Vec<String>, String), _, _| {
+        let is_banners_changed = changes.iter().any(|x| {
+            let path = format!("{}{}", prefix, x);
+            path == GNOME_BANNERS_KEY || (path.ends_with('/') && GNOME_BANNERS_KEY.starts_with(&path))
+        });
+        if is_banners_changed {
+            is_changed.set(true);
+        }
+        true
+    }) {
+        tracing::error!("Session State Provider can not subscribe to dconf changes: {}", e);
+    }
+}
+
+fn subscribe(connection: &LocalConnection, rule: MatchRule<'static>, is_changed: &Rc<Cell<bool>>) {
+    let is_changed = is_changed.clone();
+    if let Err(e) = connection.add_match(rule, move |_: (), _, _| {
+        is_changed.set(true);
+        true
+    }) {
+        tracing::error!("Session State Provider can not subscribe to D-Bus signals: {}", e);
+    }
+}
+
+fn send_data(state: u8, data_sender: &broadcast::Sender<Vec<u8>>) {
+    tracing::info!("new session state: {:#05b}", state);
+    if let Err(e) = data_sender.send(vec![DataType::SessionState as u8, state]) {
+        tracing::error!("Session State Provider failed to send data: {:?}", e);
+    }
+}
+
+pub struct SessionStateProvider {
+    data_sender: broadcast::Sender<Vec<u8>>,
+    is_started: Arc<AtomicBool>,
+    is_locked_sender: Option<mpsc::Sender<bool>>,
+}
+
+impl SessionStateProvider {
+    /// When `is_locked_sender` is set, lock state is also sent there to pause other providers
+    pub fn new(data_sender: broadcast::Sender<Vec<u8>>, is_locked_sender: Option<mpsc::Sender<bool>>) -> Box<dyn Provider> {
+        let provider = SessionStateProvider {
+            data_sender,
+            is_started: Arc::new(AtomicBool::new(false)),
+            is_locked_sender,
+        };
+        return Box::new(provider);
+    }
+}
+
+impl Provider for SessionStateProvider {
+    fn start(&self) {
+        tracing::info!("Session State Provider started");
+        self.is_started.store(true, Relaxed);
+        let data_sender = self.data_sender.clone();
+        let is_started = self.is_started.clone();
+        let is_locked_sender = self.is_locked_sender.clone();
+        std::thread::spawn(move || {
+            let (system, session) = match (LocalConnection::new_system(), LocalConnection::new_session()) {
+                (Ok(system), Ok(session)) => (system, session),
+                (Err(e), _) | (_, Err(e)) => return tracing::error!("Session State Provider can not connect to D-Bus: {}", e),
+            };
+
+            let is_changed = Rc::new(Cell::new(true));
+            let session_path = get_session_path(&system);
+            if let Some(session_path) = &session_path {
+                let rule = MatchRule::new_signal("org.freedesktop.DBus.Properties", "PropertiesChanged").with_path(session_path.clone());
+                subscribe(&system, rule, &is_changed);
+            }
+            for (_, _, interface) in SCREENSAVERS {
+                subscribe(&session, MatchRule::new_signal(interface, "ActiveChanged"), &is_changed);
+            }
+            let rule = MatchRule::new_signal("org.freedesktop.DBus.Properties", "PropertiesChanged").with_path(NOTIFICATIONS_PATH);
+            subscribe(&session, rule, &is_changed);
+
+            let is_gnome = std::env::var("XDG_CURRENT_DESKTOP")
+                .unwrap_or_default()
+                .to_uppercase()
+                .contains("GNOME");
+            let is_banners_changed = Rc::new(Cell::new(is_gnome));
+            if is_gnome {
+                subscribe_gnome_banners(&session, &is_banners_changed);
+            }
+
+            let mut are_banners_hidden = false;
+            let mut synced_state: Option<u8> = None;
+            loop {
+                if !is_started.load(Relaxed) {
+                    break;
+                }
+
+                if is_banners_changed.replace(false) {
+                    are_banners_hidden = are_gnome_banners_hidden();
+                    is_changed.set(true);
+                }
+
+                if is_changed.replace(false) {
+                    let is_locked = session_path.as_ref().is_some_and(|x| is_locked(&system, x));
+                    let state = (if is_locked { LOCKED } else { 0 })
+                        | (if is_idle(&session) { IDLE } else { 0 })
+                        | (if is_do_not_disturb(&session, are_banners_hidden) {
+                            DO_NOT_DISTURB
+                        } else {
+                            0
+                        });
+
+                    if synced_state != Some(state) {
+                        if let Some(is_locked_sender) = &is_locked_sender {
+                            if synced_state.is_none_or(|x| x & LOCKED != state & LOCKED) {
+                                let _ = is_locked_sender.blocking_send(is_locked);
+                            }
+                        }
+                        send_data(state, &data_sender);
+                        synced_state = Some(state);
+                    }
+                }
+
+                if let Err(e) = system
+                    .process(Duration::from_millis(500))
+                    .and(session.process(Duration::from_millis(500)))
+                {
+                    tracing::error!("Session State Provider lost connection to D-Bus: {}", e);
+                    break;
+                }
+            }
+
+            tracing::info!("Session State Provider stopped");
+        });
+    }
+
+    fn stop(&self) {
+        self.is_started.store(false, Relaxed);
+    }
+}
@@ -0,0 +1,199 @@
+use chrono::{DateTime, Duration, DurationRound, Local, NaiveDate, Timelike, Utc};
+use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+use crate::config::SunConfig;
+use crate::data_type::DataType;
+
+use super::_base::Provider;
+
+const PHASE_NIGHT: u8 = 0;
+const PHASE_TWILIGHT: u8 = 1;
+const PHASE_DAY: u8 = 2;
+
+/// Sun is below the horizon at sunrise because of refraction and its radius
+const SUNRISE_ALTITUDE: f64 = -0.833;
+const CIVIL_TWILIGHT_ALTITUDE: f64 = -6.0;
+
+const J2000: f64 = 2451545.0;
+const UNIX_EPOCH_JULIAN_DAY: f64 = 2440587.5;
+
+/// Time when the sun crosses the altitude in the morning and in the evening,
+/// `Err(true)` if it is always above (polar day) and `Err(false)` if it is always below (polar night)
+type Crossing = Result<(DateTime<Utc>, DateTime<Utc>), bool>;
+
+fn from_julian_day(value: f64) -> Option<DateTime<Utc>> {
+    return DateTime::from_timestamp(((value - UNIX_EPOCH_JULIAN_DAY) * 86400.0).round() as i64, 0);
+}
+
+/// Sunrise equation (https://en.wikipedia.org/wiki/Sunrise_equation), accurate to a minute or two
+fn get_crossing(date: NaiveDate, latitude: f64, longitude: f64, altitude: f64) -> Crossing {
+    let day = (date - DateTime::UNIX_EPOCH.date_naive()).num_days() as f64 + UNIX_EPOCH_JULIAN_DAY + 0.5 - J2000;
+    let mean_solar_time = day - longitude / 360.0;
+    let anomaly = (357.5291 + 0.98560028 * mean_solar_time).rem_euclid(360.0).to_radians();
+    let center = 1.9148 * anomaly.sin() + 0.02 * (2.0 * anomaly).sin() + 0.0003 * (3.0 * anomaly).sin();
+    let ecliptic_longitude = (anomaly.to_degrees() + center + 180.0 + 102.9372).rem_euclid(360.0).to_radians();
+    let transit = J2000 + mean_solar_time + 0.0053 * anomaly.sin() - 0.0069 * (2.0 * ecliptic_longitude).sin();
+    let declination = (ecliptic_longitude.sin() * 23.4397f64.to_radians().sin()).asin();
+
+    let latitude = latitude.to_radians();
+    let hour_angle_cos = (altitude.to_radians().sin() - latitude.sin() * declination.sin()) / (latitude.cos() * declination.cos());
+    if hour_angle_cos < -1.0 {
+        return Err(true);
+    }
+    if hour_angle_cos > 1.0 {
+        return Err(false);
+    }
+
+    let hour_angle = hour_angle_cos.acos().to_degrees();
+    return match (
+        from_julian_day(transit - hour_angle / 360.0),
+        from_julian_day(transit + hour_angle / 360.0),
+    ) {
+        (Some(rise), Some(set)) => Ok((rise, set)),
+        _ => Err(false),
+    };
+}
+
+fn is_above(crossing: &Crossing, now: DateTime<Utc>) -> bool {
+    return match crossing {
+        Ok((rise, set)) => *rise <= now && now < *set,
+        Err(is_always_above) => *is_always_above,
+    };
+}
+
+/// `[phase, dawn, sunrise, sunset, dusk]`, each time is 2 bytes (hour and minute) in local time, 0xFF if there is none
+fn get_sun_data(now: DateTime<Local>, latitude: f64, longitude: f64) -> Vec<u8> {
+    let sunrise = get_crossing(now.date_naive(), latitude, longitude, SUNRISE_ALTITUDE);
+    let twilight = get_crossing(now.date_naive(), latitude, longitude, CIVIL_TWILIGHT_ALTITUDE);
+    let phase = if is_above(&sunrise, now.to_utc()) {
+        PHASE_DAY
+    } else if is_above(&twilight, now.to_utc()) {
+        PHASE_TWILIGHT
+    } else {
+        PHASE_NIGHT
+    };
+
+    let mut data = vec![phase];
+    for time in [
+        twilight.map(|x| x.0),
+        sunrise.map(|x| x.0),
+        sunrise.map(|x| x.1),
+        twilight.map(|x| x.1),
+    ] {
+        match time.map(|x| x.with_timezone(&Local)) {
+            Ok(time) => data.extend_from_slice(&[time.hour() as u8, time.minute() as u8]),
+            Err(_) => data.extend_from_slice(&[u8::MAX, u8::MAX]),
+        }
+    }
+    return data;
+}
+
+/// Returns when sun data can change next: at dawn, sunrise, sunset or dusk of today, otherwise at the next full hour,
+/// as times of the next day are used after midnight and they are shown in local time which changes with DST
+fn get_next_change(now: DateTime<Local>, latitude: f64, longitude: f64) -> DateTime<Utc> {
+    let next_hour = now
+        .duration_trunc(Duration::hours(1))
+        .map(|x| x + Duration::hours(1))
+        .unwrap_or(now + Duration::hours(1));
+    return [SUNRISE_ALTITUDE, CIVIL_TWILIGHT_ALTITUDE]
+        .iter()
+        .filter_map(|altitude| get_crossing(now.date_naive(), latitude, longitude, *altitude).ok())
+        .flat_map(|(rise, set)| [rise, set])
+        .filter(|x| *x > now.to_utc())
+        .fold(next_hour.to_utc(), DateTime::min);
+}
+
+fn send_data(value: &[u8], data_sender: &broadcast::Sender<Vec<u8>>) {
+    tracing::info!("new sun data: {:?}", value);
+    let mut data = vec![DataType::Sun as u8];
+    data.extend_from_slice(value);
+    if let Err(e) = data_sender.send(data) {
+        tracing::error!("Sun Provider failed to send data: {:?}", e);
+    }
+}
+
+pub struct SunProvider {
+    data_sender: broadcast::Sender<Vec<u8>>,
+    is_started: Arc<AtomicBool>,
+    config: SunConfig,
+}
+
+impl SunProvider {
+    pub fn new(data_sender: broadcast::Sender<Vec<u8>>, config: SunConfig) -> Box<dyn Provider> {
+        let provider = SunProvider {
+            data_sender,
+            is_started: Arc::new(AtomicBool::new(false)),
+            config,
+        };
+        return Box::new(provider);
+    }
+}
+
+impl Provider for SunProvider {
+    fn start(&self) {
+        tracing::info!("Sun Provider started");
+        self.is_started.store(true, Relaxed);
+        let data_sender = self.data_sender.clone();
+        let is_started = self.is_started.clone();
+        let (latitude, longitude) = (self.config.latitude, self.config.longitude);
+        std::thread::spawn(move || {
+            // data is only calculated when it can change, clock is still checked every second,
+            // so the change is not missed after suspend or when system time is adjusted
+            let mut synced_data: Vec<u8> = vec![];
+            let mut next_change = Utc::now();
+            loop {
+                if !is_started.load(Relaxed) {
+                    break;
+                }
+
+                let now = Local::now();
+                if now >= next_change {
+                    let data = get_sun_data(now, latitude, longitude);
+                    if synced_data != data {
+                        send_data(&data, &data_sender);
+                        synced_data = data;
+                    }
+                    next_change = get_next_change(now, latitude, longitude);
+                }
+
+                std::thread::sleep(std::time::Duration::from_secs(1));
+            }
+
+            tracing::info!("Sun Provider stopped");
+        });
+    }
+
+    fn stop(&self) {
+        self.is_started.store(false, Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HAMBURG: (f64, f64) = (53.55, 9.99);
+
+    #[test]
+    fn changes_only_at_calculated_times() {
+        let mut now = "2024-03-20T00:00:00Z".parse::<DateTime<Utc>>().unwrap().with_timezone(&Local);
+        let end = now + Duration::days(1);
+        let mut phases = vec![];
+        while now < end {
+            let data = get_sun_data(now, HAMBURG.0, HAMBURG.1);
+            let next_change = get_next_change(now, HAMBURG.0, HAMBURG.1).with_timezone(&Local);
+            assert!(next_change > now && next_change - now <= Duration::hours(1));
+            assert_eq!(get_sun_data(next_change - Duration::seconds(1), HAMBURG.0, HAMBURG.1), data);
+
+            if phases.last() != Some(&data[0]) {
+                phases.push(data[0]);
+            }
+            now = next_change;
+        }
+
+        // exact sequence depends on the local time zone, as times of the local date are used
+        assert!([PHASE_NIGHT, PHASE_TWILIGHT, PHASE_DAY].iter().all(|x| phases.contains(x)));
+    }
+}
@@ -1,19 +1,49 @@
-use chrono::{DateTime, Local, Timelike};
+use chrono::{DateTime, Datelike, Local, Locale, NaiveTime, Offset, TimeZone, Timelike};
+use chrono_tz::Tz;
 use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::broadcast;
 
+use crate::config::{ClockFormat, TimeConfig};
 use crate::data_type::DataType;
 
 use super::_base::Provider;
 
-fn get_time() -> (u8, u8) {
-    let now: DateTime<Local> = Local::now();
+fn get_time(now: &DateTime<Local>) -> (u8, u8) {
     let hour = now.hour() as u8;
     let minute = now.minute() as u8;
     return (hour, minute);
 }
 
+/// Time zone is in DST if its current offset is bigger than the standard one, which is the smallest offset of the year
+fn is_dst<Tz: TimeZone>(time: &DateTime<Tz>) -> bool {
+    let offset = time.offset().fix().local_minus_utc();
+    let standard_offset = [1, 7]
+        .iter()
+        .filter_map(|month| time.timezone().with_ymd_and_hms(time.year(), *month, 1, 0, 0, 0).earliest())
+        .map(|x| x.offset().fix().local_minus_utc())
+        .min()
+        .unwrap_or(offset);
+    return offset > standard_offset;
+}
+
+/// `[hour, minute, second, day, month, year (u16 LE), weekday (1 = Monday), DST flag, UTC offset in minutes (i16 LE)]`
+fn get_extended_time<Tz: TimeZone>(time: &DateTime<Tz>) -> Vec<u8> {
+    let mut data = vec![
+        time.hour() as u8,
+        time.minute() as u8,
+        time.second() as u8,
+        time.day() as u8,
+        time.month() as u8,
+    ];
+    data.extend_from_slice(&(time.year() as u16).to_le_bytes());
+    data.push(time.weekday().number_from_monday() as u8);
+    data.push(is_dst(time) as u8);
+    data.extend_from_slice(&((time.offset().fix().local_minus_utc() / 60) as i16).to_le_bytes());
+    return data;
+}
+
 fn send_data(value: &(u8, u8), host_to_device_sender: &broadcast::Sender<Vec<u8>>) {
     let data = vec![DataType::Time as u8, value.0, value.1];
     if let Err(e) = host_to_device_sender.send(data) {
@@ -21,16 +51,186 @@ fn send_data(value: &(u8, u8), host_to_device_sender: &broadcast::Sender<Vec<u8>
     }
 }
 
+fn send_extended_data(value: &[u8], host_to_device_sender: &broadcast::Sender<Vec<u8>>) {
+    let mut data = vec![DataType::TimeExtended as u8];
+    data.extend_from_slice(value);
+    if let Err(e) = host_to_device_sender.send(data) {
+        tracing::error!("Time Provider failed to send data: {:?}", e);
+    }
+}
+
+/// `[hour, minute, day difference with local date (i8), DST flag, UTC offset in minutes (i16 LE)]`
+fn get_world_clock(time: &DateTime<Tz>, local_time: &DateTime<Local>) -> Vec<u8> {
+    let day_difference = (time.date_naive() - local_time.date_naive()).num_days() as i8;
+    let mut data = vec![time.hour() as u8, time.minute() as u8, day_difference as u8, is_dst(time) as u8];
+    data.extend_from_slice(&((time.offset().fix().local_minus_utc() / 60) as i16).to_le_bytes());
+    return data;
+}
+
+/// `Europe/Berlin` is shown as `Berlin`, `America/Argentina/Buenos_Aires` as `Buenos Aires`
+fn get_zone_label(zone: &Tz) -> String {
+    return zone.name().rsplit('/').next().unwrap_or_default().replace('_', " ");
+}
+
+fn send_world_clock_data(index: usize, value: &[u8], zone: &Tz, host_to_device_sender: &broadcast::Sender<Vec<u8>>) {
+    let mut label = get_zone_label(zone).into_bytes();
+    label.truncate(22);
+    let mut data = vec![DataType::WorldClock as u8, index as u8];
+    data.extend_from_slice(value);
+    data.push(label.len() as u8);
+    data.extend_from_slice(&label);
+    if let Err(e) = host_to_device_sender.send(data) {
+        tracing::error!("Time Provider failed to send data: {:?}", e);
+    }
+}
+
+const CLOCK_12_HOUR: u8 = 1 << 0;
+const CLOCK_PM: u8 = 1 << 1;
+
+/// Parses POSIX (`de_DE.UTF-8@euro`), Windows (`de-DE`) and macOS (`de_DE@rg=atzzzz`) locale names
+fn parse_locale(value: &str) -> Option<Locale> {
+    let name = value.split(['.', '@']).next().unwrap_or_default().replace('-', "_");
+    return name.parse::<Locale>().ok();
+}
+
+#[cfg(not(target_os = "windows"))]
+fn get_system_locale() -> Option<Locale> {
+    let env_locale = ["LC_ALL", "LC_TIME", "LANG"]
+        .iter()
+        .filter_map(|x| std::env::var(x).ok())
+        .find(|x| !x.is_empty());
+    if let Some(locale) = env_locale.and_then(|x| parse_locale(&x)) {
+        return Some(locale);
+    }
+
+    // GUI applications on macOS usually have no locale variables
+    #[cfg(target_os = "macos")]
+    if let Ok(output) = std::process::Command::new("defaults").args(["read", "-g", "AppleLocale"]).output() {
+        return parse_locale(String::from_utf8_lossy(&output.stdout).trim());
+    }
+
+    return None;
+}
+
+#[cfg(target_os = "windows")]
+fn get_system_locale() -> Option<Locale> {
+    let mut buffer = [0u16; 85];
+    let length = unsafe { windows::Win32::Globalization::GetUserDefaultLocaleName(&mut buffer) };
+    if length <= 1 {
+        return None;
+    }
+
+    return parse_locale(&String::from_utf16_lossy(&buffer[..length as usize - 1]));
+}
+
+/// Locale uses 12-hour clock if its time format does not show 13 for 1 PM
+fn is_12_hour_locale(locale: Locale) -> bool {
+    let time = NaiveTime::from_hms_opt(13, 0, 0).unwrap_or_default();
+    let date_time = Local::now().with_time(time).earliest().unwrap_or(Local::now());
+    return !date_time.format_localized("%X", locale).to_string().contains("13");
+}
+
+/// `[hour (1-12 for 12-hour clock), minute, flags (bit 0 = 12-hour clock, bit 1 = PM), date length, short date]`
+fn get_formatted_time(time: &DateTime<Local>, is_12_hour: bool, locale: Locale) -> Vec<u8> {
+    let (is_pm, hour_12) = time.hour12();
+    let hour = if is_12_hour { hour_12 } else { time.hour() };
+    let flags = (if is_12_hour { CLOCK_12_HOUR } else { 0 }) | (if is_pm { CLOCK_PM } else { 0 });
+    let mut date = time.format_localized("%x", locale).to_string().into_bytes();
+    date.truncate(26);
+    let mut data = vec![hour as u8, time.minute() as u8, flags, date.len() as u8];
+    data.extend_from_slice(&date);
+    return data;
+}
+
+fn send_formatted_data(value: &[u8], host_to_device_sender: &broadcast::Sender<Vec<u8>>) {
+    let mut data = vec![DataType::TimeFormatted as u8];
+    data.extend_from_slice(value);
+    if let Err(e) = host_to_device_sender.send(data) {
+        tracing::error!("Time Provider failed to send data: {:?}", e);
+    }
+}
+
+/// Sleeps until the start of the next second, so values are sent right when they change
+fn sleep_until_next_second() {
+    let millis = Local::now().timestamp_subsec_millis().min(999);
+    std::thread::sleep(Duration::from_millis(1000 - millis as u64 + 1));
+}
+
+/// Keeps the last sent values, so each frame is sent only when it changes
+struct Clock {
+    is_extended: bool,
+    /// `Some(true)` for 12-hour clock, formatted time is not sent if `None`
+    clock_format: Option<bool>,
+    locale: Locale,
+    world_clocks: Vec<Option<Tz>>,
+    synced_time: (u8, u8),
+    synced_extended_time: Vec<u8>,
+    synced_formatted_time: Vec<u8>,
+    synced_world_clocks: Vec<Vec<u8>>,
+}
+
+impl Clock {
+    fn new(is_extended: bool, clock_format: Option<bool>, locale: Locale, world_clocks: Vec<Option<Tz>>) -> Self {
+        return Self {
+            is_extended,
+            clock_format,
+            locale,
+            synced_time: (0, 0),
+            synced_extended_time: vec![],
+            synced_formatted_time: vec![],
+            synced_world_clocks: vec![vec![]; world_clocks.len()],
+            world_clocks,
+        };
+    }
+
+    /// Changed frames are sent back to back, host to device channel keeps all of them until the device writes them
+    fn send_changes(&mut self, now: &DateTime<Local>, is_clock_changed: bool, host_to_device_sender: &broadcast::Sender<Vec<u8>>) {
+        let time = get_time(now);
+        if self.synced_time != time || is_clock_changed {
+            self.synced_time = time;
+            send_data(&self.synced_time, host_to_device_sender);
+        }
+
+        if self.is_extended {
+            let extended_time = get_extended_time(now);
+            if self.synced_extended_time != extended_time || is_clock_changed {
+                send_extended_data(&extended_time, host_to_device_sender);
+                self.synced_extended_time = extended_time;
+            }
+        }
+
+        if let Some(is_12_hour) = self.clock_format {
+            let formatted_time = get_formatted_time(now, is_12_hour, self.locale);
+            if self.synced_formatted_time != formatted_time || is_clock_changed {
+                send_formatted_data(&formatted_time, host_to_device_sender);
+                self.synced_formatted_time = formatted_time;
+            }
+        }
+
+        for (index, zone) in self.world_clocks.iter().enumerate() {
+            if let Some(zone) = zone {
+                let world_clock = get_world_clock(&now.with_timezone(zone), now);
+                if self.synced_world_clocks[index] != world_clock || is_clock_changed {
+                    send_world_clock_data(index, &world_clock, zone, host_to_device_sender);
+                    self.synced_world_clocks[index] = world_clock;
+                }
+            }
+        }
+    }
+}
+
 pub struct TimeProvider {
     host_to_device_sender: broadcast::Sender<Vec<u8>>,
     is_started: Arc<AtomicBool>,
+    config: TimeConfig,
 }
 
 impl TimeProvider {
-    pub fn new(host_to_device_sender: broadcast::Sender<Vec<u8>>) -> Box<dyn Provider> {
+    pub fn new(host_to_device_sender: broadcast::Sender<Vec<u8>>, config: TimeConfig) -> Box<dyn Provider> {
         let provider = TimeProvider {
             host_to_device_sender,
             is_started: Arc::new(AtomicBool::new(false)),
+            config,
         };
         return Box::new(provider);
     }
@@ -42,20 +242,49 @@ impl Provider for TimeProvider {
         self.is_started.store(true, Relaxed);
         let host_to_device_sender = self.host_to_device_sender.clone();
         let is_started = self.is_started.clone();
+        let is_extended = self.config.extended.unwrap_or(false);
+        // index in config is kept for invalid zones, so firmware can rely on it
+        let world_clocks = self.config.world_clocks.iter().flatten().map(|x| {
+            x.parse::<Tz>()
+                .map_err(|e| tracing::error!("Time Provider: invalid world clock time zone '{}': {}", x, e))
+                .ok()
+        });
+        let world_clocks = world_clocks.collect::<Vec<_>>();
+        let locale = match &self.config.locale {
+            Some(locale) => parse_locale(locale).or_else(|| {
+                tracing::error!("Time Provider: unknown locale '{}'", locale);
+                None
+            }),
+            None => get_system_locale(),
+        };
+        let locale = locale.unwrap_or(Locale::POSIX);
+        let clock_format = self.config.clock_format.map(|x| match x {
+            ClockFormat::Auto => is_12_hour_locale(locale),
+            ClockFormat::Hour12 => true,
+            ClockFormat::Hour24 => false,
+        });
+        let mut clock = Clock::new(is_extended, clock_format, locale, world_clocks);
         std::thread::spawn(move || {
-            let mut synced_time = (0u8, 0u8);
+            let mut checked_at: Option<(Instant, DateTime<Local>)> = None;
             loop {
                 if !is_started.load(Relaxed) {
                     break;
                 }
 
-                let time = get_time();
-                if synced_time != time {
-                    synced_time = time;
-                    send_data(&synced_time, &host_to_device_sender);
+                // system clock change is detected as a difference between wall clock and monotonic clock,
+                // time zone change is detected by UTC offset, in both cases everything is sent again
+                let now = Local::now();
+                let is_clock_changed = checked_at.is_some_and(|(instant, time)| {
+                    let drift = (now - time).num_milliseconds() - instant.elapsed().as_millis() as i64;
+                    drift.abs() > 1000 || time.offset().fix() != now.offset().fix()
+                });
+                checked_at = Some((Instant::now(), now));
+                if is_clock_changed {
+                    tracing::info!("system clock or time zone was changed");
                 }
 
-                std::thread::sleep(std::time::Duration::from_millis(100));
+                clock.send_changes(&now, is_clock_changed, &host_to_device_sender);
+                sleep_until_next_second();
             }
 
             tracing::info!("Time Provider stopped");
@@ -66,3 +295,96 @@ impl Provider for TimeProvider {
         self.is_started.store(false, Relaxed);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    /// Returns DST flag and UTC offset in minutes from the extended time frame
+    fn get_dst_state(zone: Tz, utc: &str) -> (bool, i16) {
+        let time = utc.parse::<DateTime<Utc>>().unwrap().with_timezone(&zone);
+        let data = get_extended_time(&time);
+        return (data[8] != 0, i16::from_le_bytes([data[9], data[10]]));
+    }
+
+    #[test]
+    fn detects_dst_in_northern_zone() {
+        let zone = chrono_tz::Europe::Berlin;
+        // spring forward at 01:00 UTC on the last Sunday of March, fall back on the last Sunday of October
+        assert_eq!(get_dst_state(zone, "2024-03-31T00:59:59Z"), (false, 60));
+        assert_eq!(get_dst_state(zone, "2024-03-31T01:00:00Z"), (true, 120));
+        assert_eq!(get_dst_state(zone, "2024-10-27T00:59:59Z"), (true, 120));
+        assert_eq!(get_dst_state(zone, "2024-10-27T01:00:00Z"), (false, 60));
+    }
+
+    #[test]
+    fn detects_dst_in_southern_zone() {
+        let zone = chrono_tz::Australia::Sydney;
+        // DST ends on the first Sunday of April and starts on the first Sunday of October, at 16:00 UTC the day before
+        assert_eq!(get_dst_state(zone, "2024-04-06T15:59:59Z"), (true, 660));
+        assert_eq!(get_dst_state(zone, "2024-04-06T16:00:00Z"), (false, 600));
+        assert_eq!(get_dst_state(zone, "2024-10-05T15:59:59Z"), (false, 600));
+        assert_eq!(get_dst_state(zone, "2024-10-05T16:00:00Z"), (true, 660));
+    }
+
+    #[test]
+    fn detects_no_dst_without_transitions() {
+        assert_eq!(get_dst_state(chrono_tz::Asia::Tokyo, "2024-07-01T00:00:00Z"), (false, 540));
+        assert_eq!(get_dst_state(chrono_tz::UTC, "2024-07-01T00:00:00Z"), (false, 0));
+    }
+
+    #[test]
+    fn sends_extended_time_fields() {
+        let time = "2024-03-31T01:30:15Z"
+            .parse::<DateTime<Utc>>()
+            .unwrap()
+            .with_timezone(&chrono_tz::Europe::Berlin);
+        // 03:30:15 on Sunday 31.03.2024
+        assert_eq!(get_extended_time(&time), vec![3, 30, 15, 31, 3, 0xE8, 0x07, 7, 1, 120, 0]);
+    }
+
+    /// Returns data types of all frames sent in one tick, fails if any frame was dropped by the channel
+    fn get_tick_frames(clock: &mut Clock, now: &DateTime<Local>) -> Vec<u8> {
+        let (host_to_device_sender, mut host_to_device_receiver) = broadcast::channel(crate::HOST_TO_DEVICE_CAPACITY);
+        clock.send_changes(now, false, &host_to_device_sender);
+        let mut data_types = vec![];
+        loop {
+            match host_to_device_receiver.try_recv() {
+                Ok(data) => data_types.push(data[0]),
+                Err(broadcast::error::TryRecvError::Empty) => return data_types,
+                Err(e) => panic!("frame was not received: {:?}", e),
+            }
+        }
+    }
+
+    #[test]
+    fn sends_every_time_frame_of_a_tick() {
+        let now = Local.with_ymd_and_hms(2024, 3, 31, 13, 30, 15).earliest().unwrap();
+        let mut clock = Clock::new(true, Some(true), Locale::en_US, vec![]);
+        let time_frames = vec![DataType::Time as u8, DataType::TimeExtended as u8, DataType::TimeFormatted as u8];
+        assert_eq!(get_tick_frames(&mut clock, &now), time_frames);
+
+        // only changed frames are sent on the next second
+        let now = now + chrono::Duration::seconds(1);
+        assert_eq!(get_tick_frames(&mut clock, &now), vec![DataType::TimeExtended as u8]);
+    }
+
+    #[test]
+    fn sends_every_world_clock_of_a_tick() {
+        let now = Local.with_ymd_and_hms(2024, 3, 31, 13, 30, 15).earliest().unwrap();
+        let zones = vec![Some(chrono_tz::Asia::Tokyo), None, Some(chrono_tz::America::New_York)];
+        let mut clock = Clock::new(false, None, Locale::POSIX, zones);
+        let (host_to_device_sender, mut host_to_device_receiver) = broadcast::channel(crate::HOST_TO_DEVICE_CAPACITY);
+        clock.send_changes(&now, false, &host_to_device_sender);
+
+        assert_eq!(host_to_device_receiver.try_recv().unwrap()[0], DataType::Time as u8);
+        // invalid zone keeps its index, so the zone after it is sent with index 2
+        for (index, label) in [(0u8, "Tokyo"), (2, "New York")] {
+            let data = host_to_device_receiver.try_recv().unwrap();
+            assert_eq!(data[..2], [DataType::WorldClock as u8, index]);
+            assert!(data.ends_with(label.as_bytes()));
+        }
+        assert!(host_to_device_receiver.try_recv().is_err());
+    }
+}
@@ -0,0 +1,236 @@
+use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, oneshot};
+
+use crate::config::TimerConfig;
+use crate::data_type::DataType;
+use crate::utils::command_receiver::{CommandReceiver, Received};
+
+use super::_base::Provider;
+
+const COMMAND_START: u8 = 0;
+const COMMAND_PAUSE: u8 = 1;
+const COMMAND_RESET: u8 = 2;
+const COMMAND_TOGGLE: u8 = 3;
+
+const STATE_STOPPED: u8 = 0;
+const STATE_RUNNING: u8 = 1;
+const STATE_PAUSED: u8 = 2;
+
+#[derive(Clone, Copy)]
+enum Timer {
+    Stopped,
+    Running { ends_at: Instant, duration: Duration },
+    Paused { remaining: Duration, duration: Duration },
+}
+
+impl Timer {
+    /// Returns state and remaining time, stopped timer shows configured duration
+    fn get_state(&self, default_duration: Duration) -> (u8, Duration) {
+        return match self {
+            Timer::Stopped => (STATE_STOPPED, default_duration),
+            Timer::Running { ends_at, .. } => (STATE_RUNNING, ends_at.saturating_duration_since(Instant::now())),
+            Timer::Paused { remaining, .. } => (STATE_PAUSED, *remaining),
+        };
+    }
+
+    /// `[COMMAND_START, duration_lo, duration_hi]` - duration in seconds is optional, paused timer is resumed
+    fn handle_command(self, command: &[u8], default_duration: Duration) -> Timer {
+        let start = |timer: Timer| match timer {
+            Timer::Paused { remaining, duration } => Timer::Running {
+                ends_at: Instant::now() + remaining,
+                duration,
+            },
+            _ => {
+                let seconds = u16::from_le_bytes([*command.get(1).unwrap_or(&0), *command.get(2).unwrap_or(&0)]);
+                let duration = if seconds > 0 {
+                    Duration::from_secs(seconds as u64)
+                } else {
+                    default_duration
+                };
+                Timer::Running {
+                    ends_at: Instant::now() + duration,
+                    duration,
+                }
+            }
+        };
+        let pause = |timer: Timer| match timer {
+            Timer::Running { ends_at, duration } => Timer::Paused {
+                remaining: ends_at.saturating_duration_since(Instant::now()),
+                duration,
+            },
+            _ => timer,
+        };
+
+        return match command.first() {
+            Some(&COMMAND_START) => start(self),
+            Some(&COMMAND_PAUSE) => pause(self),
+            Some(&COMMAND_RESET) => Timer::Stopped,
+            Some(&COMMAND_TOGGLE) if matches!(self, Timer::Running { .. }) => pause(self),
+            Some(&COMMAND_TOGGLE) => start(self),
+            _ => self,
+        };
+    }
+}
+
+fn send_data(state: u8, seconds: u64, data_sender: &broadcast::Sender<Vec<u8>>) {
+    let mut data = vec![DataType::Timer as u8, state];
+    data.extend_from_slice(&(seconds.min(u16::MAX as u64) as u16).to_le_bytes());
+    if let Err(e) = data_sender.send(data) {
+        tracing::error!("Timer Provider failed to send data: {:?}", e);
+    }
+}
+
+fn send_done(duration: Duration, data_sender: &broadcast::Sender<Vec<u8>>) {
+    tracing::info!("timer is done after {} seconds", duration.as_secs());
+    let mut data = vec![DataType::TimerDone as u8];
+    data.extend_from_slice(&(duration.as_secs().min(u16::MAX as u64) as u16).to_le_bytes());
+    if let Err(e) = data_sender.send(data) {
+        tracing::error!("Timer Provider failed to send data: {:?}", e);
+    }
+}
+
+/// Waits for the notifier, so it is run on its own thread and the timer keeps ticking meanwhile
+fn notify(duration: Duration) {
+    let message = format!("{} minute timer is done", duration.as_secs().div_ceil(60));
+
+    #[cfg(target_os = "linux")]
+    let result = std::process::Command::new("notify-send").args(["qmk-hid-host", &message]).status();
+
+    #[cfg(target_os = "macos")]
+    let result = std::process::Command::new("osascript")
+        .args(["-e", &format!("display notification \"{}\" with title \"qmk-hid-host\"", message)])
+        .status();
+
+    // toast notifications need an app id registered by an installer, so on Windows it is only logged
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    let result: std::io::Result<()> = {
+        tracing::warn!("Timer Provider: notifications are not supported, {}", message);
+        Ok(())
+    };
+
+    if let Err(e) = result {
+        tracing::error!("Timer Provider failed to send notification: {}", e);
+    }
+}
+
+pub struct TimerProvider {
+    host_to_device_sender: broadcast::Sender<Vec<u8>>,
+    device_to_host_sender: broadcast::Sender<Vec<u8>>,
+    is_started: Arc<AtomicBool>,
+    config: TimerConfig,
+    /// Timer keeps running when providers are restarted, e.g. after another keyboard is connected
+    timer: Arc<Mutex<Timer>>,
+    /// Dropped on stop to wake up the timer thread
+    command_stop_sender: Mutex<Option<oneshot::Sender<()>>>,
+}
+
+impl TimerProvider {
+    pub fn new(
+        host_to_device_sender: broadcast::Sender<Vec<u8>>,
+        device_to_host_sender: broadcast::Sender<Vec<u8>>,
+        config: TimerConfig,
+    ) -> Box<dyn Provider> {
+        let provider = TimerProvider {
+            host_to_device_sender,
+            device_to_host_sender,
+            is_started: Arc::new(AtomicBool::new(false)),
+            config,
+            timer: Arc::new(Mutex::new(Timer::Stopped)),
+            command_stop_sender: Mutex::new(None),
+        };
+        return Box::new(provider);
+    }
+}
+
+impl Provider for TimerProvider {
+    fn start(&self) {
+        tracing::info!("Timer Provider started");
+        self.is_started.store(true, Relaxed);
+        let data_sender = self.host_to_device_sender.clone();
+        let is_started = self.is_started.clone();
+        let timer = self.timer.clone();
+        let default_duration = Duration::from_secs(self.config.duration.unwrap_or(25 * 60) as u64);
+        let is_notify_enabled = self.config.notify.unwrap_or(false);
+        let (mut command_receiver, command_stop_sender) = match CommandReceiver::new(&self.device_to_host_sender, DataType::TimerCommand) {
+            Ok(command_receiver) => command_receiver,
+            Err(e) => return tracing::error!("Timer Provider can not receive commands: {}", e),
+        };
+        *self.command_stop_sender.lock().unwrap() = Some(command_stop_sender);
+        std::thread::spawn(move || {
+            let mut synced_state: Option<(u8, u64)> = None;
+            loop {
+                if !is_started.load(Relaxed) {
+                    break;
+                }
+
+                let (state, remaining) = {
+                    let mut timer = timer.lock().unwrap();
+                    if let Timer::Running { ends_at, duration } = *timer {
+                        if ends_at <= Instant::now() {
+                            *timer = Timer::Stopped;
+                            send_done(duration, &data_sender);
+                            if is_notify_enabled {
+                                std::thread::spawn(move || notify(duration));
+                            }
+                        }
+                    }
+
+                    // running timer is sent every second, remaining seconds are rounded up,
+                    // so it shows full duration right after start and 0 only when it is done
+                    timer.get_state(default_duration)
+                };
+                let seconds = remaining.as_millis().div_ceil(1000) as u64;
+                if synced_state != Some((state, seconds)) {
+                    synced_state = Some((state, seconds));
+                    send_data(state, seconds, &data_sender);
+                }
+
+                // running timer is woken up when its rounded remaining seconds change, otherwise only by commands
+                let timeout = (state == STATE_RUNNING).then(|| remaining.saturating_sub(Duration::from_secs(seconds.saturating_sub(1))));
+                match command_receiver.recv(timeout) {
+                    Received::Command(command) => {
+                        let mut timer = timer.lock().unwrap();
+                        *timer = timer.handle_command(&command, default_duration);
+                    }
+                    Received::Timeout => {}
+                    Received::Stopped => break,
+                }
+            }
+
+            tracing::info!("Timer Provider stopped");
+        });
+    }
+
+    fn stop(&self) {
+        self.is_started.store(false, Relaxed);
+        self.command_stop_sender.lock().unwrap().take();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn handles_commands() {
+        let default_duration = Duration::from_secs(1500);
+        let timer = Timer::Stopped.handle_command(&[COMMAND_START, 60, 0], default_duration);
+        assert!(matches!(timer, Timer::Running { duration, .. } if duration == Duration::from_secs(60)));
+
+        let timer = timer.handle_command(&[COMMAND_TOGGLE], default_duration);
+        let (state, remaining) = timer.get_state(default_duration);
+        assert_eq!(state, STATE_PAUSED);
+        assert!(remaining <= Duration::from_secs(60) && remaining > Duration::from_secs(59));
+
+        // resumed timer keeps remaining time instead of starting with the new duration
+        let timer = timer.handle_command(&[COMMAND_START, 10, 0], default_duration);
+        assert!(matches!(timer, Timer::Running { duration, .. } if duration == Duration::from_secs(60)));
+
+        let timer = timer.handle_command(&[COMMAND_RESET], default_duration);
+        assert_eq!(timer.get_state(default_duration), (STATE_STOPPED, default_duration));
+        let timer = timer.handle_command(&[COMMAND_TOGGLE], default_duration);
+        assert!(matches!(timer, Timer::Running { duration, .. } if duration == default_duration));
+    }
+}
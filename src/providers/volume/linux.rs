@@ -1,5 +1,6 @@
-use libpulse_binding::context::subscribe::Facility;
-use pulsectl::controllers::{DeviceControl, SinkController};
+use libpulse_binding::context::subscribe::{Facility, InterestMaskSet};
+use pulsectl::controllers::types::DeviceInfo;
+use pulsectl::controllers::{DeviceControl, SinkController, SourceController};
 use std::ops::Deref;
 use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
 use std::sync::Arc;
@@ -9,21 +10,37 @@ use crate::data_type::DataType;
 
 use super::super::_base::Provider;
 
-fn get_volume() -> Option<f32> {
+fn get_device_volume(device: &DeviceInfo) -> Option<(f32, bool)> {
+    let device_volume = device.volume.get().first()?.0 as f32;
+    let base_volume = device.base_volume.0 as f32;
+    return Some((device_volume / base_volume, device.mute));
+}
+
+fn get_volume() -> Option<(f32, bool)> {
     let mut controller = SinkController::create().ok()?;
     if let Ok(default) = controller.get_default_device() {
-        let device_volume = default.volume.get().first()?.0 as f32;
-        let base_volume = default.base_volume.0 as f32;
-        return Some(device_volume / base_volume);
+        return get_device_volume(&default);
     }
 
     return None;
 }
 
-fn send_data(value: &f32, push_sender: &broadcast::Sender<Vec<u8>>) {
-    let volume = (value * 100.0).round() as u8;
-    let data = vec![DataType::Volume as u8, volume];
-    push_sender.send(data).unwrap();
+fn get_microphone_volume() -> Option<(f32, bool)> {
+    let mut controller = SourceController::create().ok()?;
+    if let Ok(default) = controller.get_default_device() {
+        return get_device_volume(&default);
+    }
+
+    return None;
+}
+
+/// Volume and microphone frames are sent back to back, host to device channel keeps both until the device writes them
+fn send_data(data_type: DataType, value: &(f32, bool), push_sender: &broadcast::Sender<Vec<u8>>) {
+    let volume = (value.0 * 100.0).round() as u8;
+    let data = vec![data_type as u8, volume, value.1 as u8];
+    if let Err(e) = push_sender.send(data) {
+        tracing::error!("Volume Provider failed to send data: {:?}", e);
+    }
 }
 
 pub struct VolumeProvider {
@@ -49,21 +66,36 @@ impl Provider for VolumeProvider {
         let is_started = self.is_started.clone();
 
         let mut volume = get_volume().unwrap_or_default();
-        send_data(&volume, &self.data_sender);
+        send_data(DataType::Volume, &volume, &self.data_sender);
+        let mut microphone_volume = get_microphone_volume().unwrap_or_default();
+        send_data(DataType::Microphone, &microphone_volume, &self.data_sender);
 
         std::thread::spawn(move || {
             let controller = SinkController::create().map_err(|e| tracing::error!("{}", e)).unwrap();
             let mut ctx = controller.handler.context.deref().borrow_mut();
 
-            ctx.set_subscribe_callback(Some(Box::new(move |_, _, _| {
-                let new_volume = get_volume().unwrap_or_default();
-                if volume != new_volume {
-                    volume = new_volume;
-                    send_data(&volume, &data_sender);
+            ctx.set_subscribe_callback(Some(Box::new(move |facility, _, _| {
+                // default device changes are reported as server events
+                let is_server = facility == Some(Facility::Server);
+
+                if is_server || facility == Some(Facility::Sink) {
+                    let new_volume = get_volume().unwrap_or_default();
+                    if volume != new_volume {
+                        volume = new_volume;
+                        send_data(DataType::Volume, &volume, &data_sender);
+                    }
+                }
+
+                if is_server || facility == Some(Facility::Source) {
+                    let new_microphone_volume = get_microphone_volume().unwrap_or_default();
+                    if microphone_volume != new_microphone_volume {
+                        microphone_volume = new_microphone_volume;
+                        send_data(DataType::Microphone, &microphone_volume, &data_sender);
+                    }
                 }
             })));
 
-            ctx.subscribe(Facility::Sink.to_interest_mask(), |_| {});
+            ctx.subscribe(InterestMaskSet::SINK | InterestMaskSet::SOURCE | InterestMaskSet::SERVER, |_| {});
 
             loop {
                 if !is_started.load(Relaxed) {
@@ -81,3 +113,18 @@ impl Provider for VolumeProvider {
         self.is_started.store(false, Relaxed);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sends_volume_and_microphone_frames() {
+        let (push_sender, mut push_receiver) = broadcast::channel(crate::HOST_TO_DEVICE_CAPACITY);
+        send_data(DataType::Volume, &(0.5, true), &push_sender);
+        send_data(DataType::Microphone, &(0.254, false), &push_sender);
+
+        assert_eq!(push_receiver.try_recv().unwrap(), vec![DataType::Volume as u8, 50, 1]);
+        assert_eq!(push_receiver.try_recv().unwrap(), vec![DataType::Microphone as u8, 25, 0]);
+    }
+}
@@ -1,9 +1,10 @@
 use block2::{Block, RcBlock};
 use coreaudio::audio_unit::macos_helpers::get_default_device_id;
 use coreaudio_sys::{
-    dispatch_queue_t, kAudioDevicePropertyScopeOutput, kAudioDevicePropertyVolumeScalar, kAudioHardwarePropertyDefaultOutputDevice,
-    kAudioObjectPropertyElementMain, kAudioObjectPropertyScopeGlobal, kAudioObjectPropertyScopeOutput, kAudioObjectSystemObject,
-    AudioObjectGetPropertyData, AudioObjectID, AudioObjectIsPropertySettable, AudioObjectPropertyAddress, OSStatus,
+    dispatch_queue_t, kAudioDevicePropertyMute, kAudioDevicePropertyScopeOutput, kAudioDevicePropertyVolumeScalar,
+    kAudioHardwarePropertyDefaultOutputDevice, kAudioObjectPropertyElementMain, kAudioObjectPropertyScopeGlobal,
+    kAudioObjectPropertyScopeOutput, kAudioObjectSystemObject, AudioObjectGetPropertyData, AudioObjectID, AudioObjectIsPropertySettable,
+    AudioObjectPropertyAddress, OSStatus,
 };
 use std::option::Option;
 use std::ptr;
@@ -65,6 +66,33 @@ fn get_current_volume() -> Option<f32> {
     }
 }
 
+/// Devices without mute control are reported as not muted
+fn get_current_mute() -> bool {
+    let Some(device_id) = get_default_device_id(false) else {
+        return false;
+    };
+    let mut is_muted: u32 = 0;
+    let mut property_size = size_of_val(&is_muted) as u32;
+    let property_address = AudioObjectPropertyAddress {
+        mSelector: kAudioDevicePropertyMute,
+        mScope: kAudioObjectPropertyScopeOutput,
+        mElement: kAudioObjectPropertyElementMain,
+    };
+
+    let status = unsafe {
+        AudioObjectGetPropertyData(
+            device_id,
+            &property_address,
+            0,
+            ptr::null(),
+            &mut property_size,
+            &mut is_muted as *mut _ as *mut _,
+        )
+    };
+
+    return status == 0 && is_muted != 0;
+}
+
 fn is_volume_control_supported(device_id: AudioObjectID, channel: u32) -> bool {
     let mut is_writable = 0;
     let property_address = AudioObjectPropertyAddress {
@@ -136,6 +164,24 @@ fn register_volume_listener(listener: &RcBlock<dyn Fn(u32, u64)>) {
     }
 }
 
+/// Mute is a separate property, its changes call the same block as volume changes
+fn register_mute_listener(listener: &RcBlock<dyn Fn(u32, u64)>) {
+    let Some(device_id) = get_default_device_id(false) else {
+        return;
+    };
+    let property_address = AudioObjectPropertyAddress {
+        mSelector: kAudioDevicePropertyMute,
+        mScope: kAudioObjectPropertyScopeOutput,
+        mElement: kAudioObjectPropertyElementMain,
+    };
+
+    let _ = unsafe { AudioObjectRemovePropertyListenerBlock(device_id, &property_address, ptr::null_mut(), listener) };
+    let listener_status = unsafe { AudioObjectAddPropertyListenerBlock(device_id, &property_address, ptr::null_mut(), listener) };
+    if listener_status != 0 {
+        tracing::info!("Failed to register mute listener of device {}", device_id);
+    }
+}
+
 fn register_device_change_listener(listener: &RcBlock<dyn Fn(u32, u64)>) {
     let property_address = AudioObjectPropertyAddress {
         mSelector: kAudioHardwarePropertyDefaultOutputDevice,
@@ -161,9 +207,9 @@ fn register_device_change_listener(listener: &RcBlock<dyn Fn(u32, u64)>) {
     }
 }
 
-fn send_data(value: &f32, push_sender: &broadcast::Sender<Vec<u8>>) {
-    let volume = (value * 100.0).round() as u8;
-    let data = vec![DataType::Volume as u8, volume];
+fn send_data(value: &(f32, bool), push_sender: &broadcast::Sender<Vec<u8>>) {
+    let volume = (value.0 * 100.0).round() as u8;
+    let data = vec![DataType::Volume as u8, volume, value.1 as u8];
     if let Err(e) = push_sender.send(data) {
         tracing::error!("Failed to send volume data: {}", e);
     }
@@ -180,7 +226,7 @@ impl VolumeProvider {
         let sender = data_sender.clone();
         let volume_changed_block = RcBlock::new(move |_: u32, _: u64| {
             if let Some(volume) = get_current_volume() {
-                send_data(&volume, &sender.clone());
+                send_data(&(volume, get_current_mute()), &sender.clone());
             }
         });
 
@@ -188,8 +234,9 @@ impl VolumeProvider {
         let volume_changed_block_clone = volume_changed_block.clone();
         let device_changed_block: RcBlock<dyn Fn(u32, u64)> = RcBlock::new(move |_: u32, _: u64| {
             register_volume_listener(&volume_changed_block_clone);
+            register_mute_listener(&volume_changed_block_clone);
             if let Some(volume) = get_current_volume() {
-                send_data(&volume, &sender.clone());
+                send_data(&(volume, get_current_mute()), &sender.clone());
             }
         });
 
@@ -209,6 +256,7 @@ impl Provider for VolumeProvider {
         let is_started = self.is_started.clone();
 
         register_volume_listener(&self.volume_changed_block);
+        register_mute_listener(&self.volume_changed_block);
         register_device_change_listener(&self.device_changed_block);
 
         std::thread::spawn(move || {
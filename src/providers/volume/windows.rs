@@ -17,9 +17,11 @@ use crate::data_type::DataType;
 
 use super::super::_base::Provider;
 
-fn get_volume() -> Result<f32, ()> {
-    let endpoint_volume = unsafe { get_volume_endpoint() }.map_err(|e| tracing::error!("Can not get volume endpoint: {}", e));
-    return unsafe { endpoint_volume?.GetMasterVolumeLevelScalar() }.map_err(|e| tracing::error!("Can not get volume level: {}", e));
+fn get_volume() -> Result<(f32, bool), ()> {
+    let endpoint_volume = unsafe { get_volume_endpoint() }.map_err(|e| tracing::error!("Can not get volume endpoint: {}", e))?;
+    let volume = unsafe { endpoint_volume.GetMasterVolumeLevelScalar() }.map_err(|e| tracing::error!("Can not get volume level: {}", e))?;
+    let is_muted = unsafe { endpoint_volume.GetMute() }.map_err(|e| tracing::error!("Can not get mute state: {}", e))?;
+    return Ok((volume, is_muted.as_bool()));
 }
 
 unsafe fn get_volume_endpoint() -> Result<IAudioEndpointVolume, Error> {
@@ -37,15 +39,18 @@ struct VolumeChangeCallback {
 
 impl IAudioEndpointVolumeCallback_Impl for VolumeChangeCallback {
     fn OnNotify(&self, notification_data: *mut AUDIO_VOLUME_NOTIFICATION_DATA) -> Result<(), windows::core::Error> {
-        let volume = (unsafe { *notification_data }).fMasterVolume;
-        send_data(&volume, &self.push_sender);
+        let notification_data = unsafe { *notification_data };
+        send_data(
+            &(notification_data.fMasterVolume, notification_data.bMuted.as_bool()),
+            &self.push_sender,
+        );
         return Ok(());
     }
 }
 
-fn send_data(value: &f32, push_sender: &broadcast::Sender<Vec<u8>>) {
-    let volume = (value * 100.0).round() as u8;
-    let data = vec![DataType::Volume as u8, volume];
+fn send_data(value: &(f32, bool), push_sender: &broadcast::Sender<Vec<u8>>) {
+    let volume = (value.0 * 100.0).round() as u8;
+    let data = vec![DataType::Volume as u8, volume, value.1 as u8];
     push_sender.send(data).unwrap();
 }
 
@@ -1 +1,5 @@
+pub mod command_receiver;
+pub mod hyprland_ipc;
 pub mod print_hids;
+pub mod sway_ipc;
+pub mod value_parser;
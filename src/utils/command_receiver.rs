@@ -0,0 +1,100 @@
+use std::io;
+use std::time::Duration;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::oneshot;
+
+use crate::data_type::DataType;
+
+pub enum Received {
+    /// Command without the data type byte
+    Command(Vec<u8>),
+    Timeout,
+    Stopped,
+}
+
+/// Waits for commands of one data type sent by devices, without polling the channel.
+/// Stop sender is kept by the provider and dropped on stop, which wakes up the waiting thread right away,
+/// so the thread does not outlive a quick restart of providers.
+pub struct CommandReceiver {
+    runtime: tokio::runtime::Runtime,
+    receiver: broadcast::Receiver<Vec<u8>>,
+    stop_receiver: oneshot::Receiver<()>,
+    is_stopped: bool,
+    data_type: u8,
+}
+
+impl CommandReceiver {
+    pub fn new(device_to_host_sender: &broadcast::Sender<Vec<u8>>, data_type: DataType) -> io::Result<(Self, oneshot::Sender<()>)> {
+        let (stop_sender, stop_receiver) = oneshot::channel();
+        let command_receiver = CommandReceiver {
+            runtime: tokio::runtime::Builder::new_current_thread().enable_time().build()?,
+            receiver: device_to_host_sender.subscribe(),
+            stop_receiver,
+            is_stopped: false,
+            data_type: data_type as u8,
+        };
+        return Ok((command_receiver, stop_sender));
+    }
+
+    /// Waits without a time limit if there is no timeout
+    pub fn recv(&mut self, timeout: Option<Duration>) -> Received {
+        if self.is_stopped {
+            return Received::Stopped;
+        }
+
+        let (receiver, stop_receiver, data_type) = (&mut self.receiver, &mut self.stop_receiver, self.data_type);
+        let received = self.runtime.block_on(async {
+            let sleep = async {
+                match timeout {
+                    Some(timeout) => tokio::time::sleep(timeout).await,
+                    None => std::future::pending().await,
+                }
+            };
+            tokio::pin!(sleep);
+
+            loop {
+                tokio::select! {
+                    result = receiver.recv() => match result {
+                        Ok(data) if data.first() == Some(&data_type) => return Received::Command(data[1..].to_vec()),
+                        Ok(_) => {}
+                        Err(RecvError::Lagged(count)) => tracing::warn!("{} frames from devices were skipped", count),
+                        Err(RecvError::Closed) => return Received::Stopped,
+                    },
+                    _ = &mut *stop_receiver => return Received::Stopped,
+                    _ = &mut sleep => return Received::Timeout,
+                }
+            }
+        });
+
+        self.is_stopped = matches!(received, Received::Stopped);
+        return received;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn receives_commands_until_stopped() {
+        let (device_to_host_sender, _) = broadcast::channel(16);
+        let (mut command_receiver, stop_sender) = CommandReceiver::new(&device_to_host_sender, DataType::TimerCommand).unwrap();
+
+        // commands sent in quick succession are all received, other data types are skipped
+        for command in [
+            vec![DataType::TimerCommand as u8, 1],
+            vec![DataType::RelayFromDevice as u8],
+            vec![DataType::TimerCommand as u8, 2],
+        ] {
+            device_to_host_sender.send(command).unwrap();
+        }
+        assert!(matches!(command_receiver.recv(None), Received::Command(x) if x == [1]));
+        assert!(matches!(command_receiver.recv(None), Received::Command(x) if x == [2]));
+        assert!(matches!(command_receiver.recv(Some(Duration::from_millis(10))), Received::Timeout));
+
+        let thread = std::thread::spawn(move || matches!(command_receiver.recv(None), Received::Stopped));
+        std::thread::sleep(Duration::from_millis(50));
+        drop(stop_sender);
+        assert!(thread.join().unwrap());
+    }
+}
@@ -0,0 +1,25 @@
+#![cfg(target_os = "linux")]
+use serde_json::Value;
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+
+pub fn get_socket_path(name: &str) -> Option<PathBuf> {
+    let signature = std::env::var("HYPRLAND_INSTANCE_SIGNATURE").ok()?;
+    let runtime_dir = std::env::var("XDG_RUNTIME_DIR").unwrap_or_default();
+
+    // sockets were moved from /tmp to XDG_RUNTIME_DIR in Hyprland 0.40
+    return [PathBuf::from(runtime_dir).join("hypr"), PathBuf::from("/tmp/hypr")]
+        .into_iter()
+        .map(|x| x.join(&signature).join(name))
+        .find(|x| x.exists());
+}
+
+/// Sends a command to the request socket and parses JSON response, e.g. `j/devices`
+pub fn request(command: &str) -> Option<Value> {
+    let mut stream = UnixStream::connect(get_socket_path(".socket.sock")?).ok()?;
+    stream.write_all(command.as_bytes()).ok()?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response).ok()?;
+    return serde_json::from_str::<Value>(&response).ok();
+}
@@ -0,0 +1,39 @@
+#![cfg(target_os = "linux")]
+use serde_json::Value;
+use std::io::{ErrorKind, Read, Write};
+use std::os::unix::net::UnixStream;
+
+const MAGIC: &[u8] = b"i3-ipc";
+pub const SUBSCRIBE: u32 = 2;
+pub const GET_TREE: u32 = 4;
+pub const GET_INPUTS: u32 = 100;
+pub const WINDOW_EVENT: u32 = 0x80000003;
+pub const INPUT_EVENT: u32 = 0x80000015;
+
+pub fn send_message(stream: &mut UnixStream, message_type: u32, payload: &str) -> std::io::Result<()> {
+    let mut data = MAGIC.to_vec();
+    data.extend_from_slice(&(payload.len() as u32).to_ne_bytes());
+    data.extend_from_slice(&message_type.to_ne_bytes());
+    data.extend_from_slice(payload.as_bytes());
+    return stream.write_all(&data);
+}
+
+pub fn read_message(stream: &mut UnixStream) -> std::io::Result<(u32, Value)> {
+    let mut header = [0u8; 14];
+    stream.read_exact(&mut header)?;
+    let length = u32::from_ne_bytes([header[6], header[7], header[8], header[9]]) as usize;
+    let message_type = u32::from_ne_bytes([header[10], header[11], header[12], header[13]]);
+    let mut payload = vec![0u8; length];
+    stream.read_exact(&mut payload)?;
+    return Ok((message_type, serde_json::from_slice(&payload).unwrap_or_default()));
+}
+
+/// Connects to `SWAYSOCK` and subscribes to events, e.g. `["input"]`
+pub fn connect(events: &str) -> std::io::Result<UnixStream> {
+    let socket_path = std::env::var("SWAYSOCK").map_err(|e| std::io::Error::new(ErrorKind::NotFound, e))?;
+    let mut stream = UnixStream::connect(socket_path)?;
+    send_message(&mut stream, SUBSCRIBE, events)?;
+    read_message(&mut stream)?;
+    stream.set_read_timeout(Some(std::time::Duration::from_millis(1000)))?;
+    return Ok(stream);
+}
@@ -0,0 +1,55 @@
+use serde_json::Value;
+
+use crate::config::ValueParser;
+
+/// Path is a list of object keys and array indices separated by dots, e.g. `workflow_runs.0.conclusion`
+fn get_json_value(value: &str, path: &str) -> Option<String> {
+    let json = serde_json::from_str::<Value>(value).ok()?;
+    // keys are escaped as JSON pointer tokens, so keys like `a/b` are not split
+    let pointer = path
+        .split('.')
+        .filter(|x| !x.is_empty())
+        .fold(String::new(), |x, key| x + "/" + &key.replace('~', "~0").replace('/', "~1"));
+    return match json.pointer(&pointer)? {
+        Value::String(value) => Some(value.clone()),
+        Value::Bool(value) => Some((*value as u8).to_string()),
+        Value::Null => None,
+        value => Some(value.to_string()),
+    };
+}
+
+/// Integer is returned as 4 bytes (i32 LE), string as 1-byte length and up to 30 bytes of text
+pub fn parse(value: &str, parser: ValueParser, json_path: Option<&str>) -> Option<Vec<u8>> {
+    let value = match json_path {
+        Some(path) => get_json_value(value, path)?,
+        None => value.trim().to_string(),
+    };
+
+    return match parser {
+        ValueParser::Integer => {
+            let value = value.trim().parse::<f64>().ok()?;
+            let value = value.round().clamp(i32::MIN as f64, i32::MAX as f64) as i32;
+            Some(value.to_le_bytes().to_vec())
+        }
+        ValueParser::String => {
+            let mut data = value.as_bytes().to_vec();
+            data.truncate(30);
+            data.insert(0, data.len() as u8);
+            Some(data)
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gets_json_value_by_path() {
+        let value = r#"{"workflow_runs": [{"conclusion": "success"}], "a/b": {"c~d": 5}, "flag": true}"#;
+        assert_eq!(get_json_value(value, "workflow_runs.0.conclusion").as_deref(), Some("success"));
+        assert_eq!(get_json_value(value, "a/b.c~d").as_deref(), Some("5"));
+        assert_eq!(get_json_value(value, "flag").as_deref(), Some("1"));
+        assert_eq!(get_json_value(value, "a.b"), None);
+    }
+}